@@ -1,10 +1,8 @@
 use edi_parser::{
     X12Parser,
     EdiParser,
-    InterchangeControl,
     EdiError,
     TransactionType,
-    X12Version,
 };
 use std::fs;
 
@@ -55,7 +53,7 @@ fn main() -> Result<(), EdiError> {
     println!("📦 Functional Groups: {}", interchange.functional_groups.len());
 
     for (fg_idx, fg) in interchange.functional_groups.iter().enumerate() {
-        println!("\n🏢 Functional Group {}: {}", fg_idx + 1, fg.gs_segment.elements.get(0).unwrap_or(&String::new()));
+        println!("\n🏢 Functional Group {}: {}", fg_idx + 1, fg.gs_segment.elements.first().unwrap_or(&String::new()));
 
         for (tx_idx, transaction) in fg.transactions.iter().enumerate() {
             println!("  📋 Transaction {}: {} ({}) - Type: {:?}",
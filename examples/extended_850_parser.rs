@@ -1,9 +1,7 @@
 use edi_parser::{
     X12Parser,
     EdiParser,
-    InterchangeControl,
     EdiError,
-    TransactionType,
 };
 use std::fs;
 
@@ -54,23 +52,19 @@ fn main() -> Result<(), EdiError> {
                         println!("    Purchase Order: {} (Type: {}, Purpose: {})",
                             segment.elements.get(2).unwrap_or(&"".to_string()),
                             segment.elements.get(1).unwrap_or(&"".to_string()),
-                            segment.elements.get(0).unwrap_or(&"".to_string()));
+                            segment.elements.first().unwrap_or(&"".to_string()));
                     }
-                    "PO1" => {
-                        if segment.elements.len() >= 4 {
-                            println!("    Line Item {}: {} {} at ${}",
-                                segment.elements[0],
-                                segment.elements[1],
-                                segment.elements[2],
-                                segment.elements[3]);
-                        }
+                    "PO1" if segment.elements.len() >= 4 => {
+                        println!("    Line Item {}: {} {} at ${}",
+                            segment.elements[0],
+                            segment.elements[1],
+                            segment.elements[2],
+                            segment.elements[3]);
                     }
-                    "N1" => {
-                        if segment.elements.len() >= 2 {
-                            println!("    Party {}: {}",
-                                segment.elements[0],
-                                segment.elements[1]);
-                        }
+                    "N1" if segment.elements.len() >= 2 => {
+                        println!("    Party {}: {}",
+                            segment.elements[0],
+                            segment.elements[1]);
                     }
                     _ => {}
                 }
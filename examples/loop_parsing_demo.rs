@@ -1,9 +1,7 @@
 use edi_parser::{
     X12Parser,
     EdiParser,
-    InterchangeControl,
     EdiError,
-    TransactionType,
     PurchaseOrder850,
 };
 use std::fs;
@@ -21,7 +19,7 @@ fn main() -> Result<(), EdiError> {
     parser.validate(&interchange)?;
 
     println!("=== BASIC SEGMENT-BY-SEGMENT APPROACH ===");
-    for (fg_index, fg) in interchange.functional_groups.iter().enumerate() {
+    for fg in interchange.functional_groups.iter() {
         for (tx_index, transaction) in fg.transactions.iter().enumerate() {
             println!("Transaction {}: {} - Type: {:?}",
                 tx_index + 1,
@@ -64,7 +62,7 @@ fn main() -> Result<(), EdiError> {
                             println!("PO Type: {}", po_type);
                         }
                         "DTM" => {
-                            let qualifier = segment.elements.get(0).map(|s| s.as_str()).unwrap_or("");
+                            let qualifier = segment.elements.first().map(|s| s.as_str()).unwrap_or("");
                             let date = segment.elements.get(1).map(|s| s.as_str()).unwrap_or("");
                             println!("Date ({}): {}", qualifier, date);
                         }
@@ -74,20 +72,20 @@ fn main() -> Result<(), EdiError> {
 
                 println!("\n--- Parties ---");
                 for (_i, party) in po850.party_loops.iter().enumerate() {
-                    let entity_type = party.n1_segment.elements.get(0).map(|s| s.as_str()).unwrap_or("");
+                    let entity_type = party.n1_segment.elements.first().map(|s| s.as_str()).unwrap_or("");
                     let name = party.n1_segment.elements.get(1).map(|s| s.as_str()).unwrap_or("");
                     println!("Party {}: {} - {}", _i + 1, entity_type, name);
 
                     if let Some(n4) = &party.n4_segment {
-                        let city = n4.elements.get(0).map(|s| s.as_str()).unwrap_or("");
+                        let city = n4.elements.first().map(|s| s.as_str()).unwrap_or("");
                         let state = n4.elements.get(1).map(|s| s.as_str()).unwrap_or("");
                         println!("  Location: {}, {}", city, state);
                     }
                 }
 
                 println!("\n--- Line Items ---");
-                for (_i, line_item) in po850.line_item_loops.iter().enumerate() {
-                    let line_num = line_item.po1_segment.elements.get(0).map(|s| s.as_str()).unwrap_or("");
+                for line_item in po850.line_item_loops.iter() {
+                    let line_num = line_item.po1_segment.elements.first().map(|s| s.as_str()).unwrap_or("");
                     let qty = line_item.po1_segment.elements.get(1).map(|s| s.as_str()).unwrap_or("");
                     let uom = line_item.po1_segment.elements.get(2).map(|s| s.as_str()).unwrap_or("");
                     let price = line_item.po1_segment.elements.get(3).map(|s| s.as_str()).unwrap_or("");
@@ -1,6 +1,8 @@
 use thiserror::Error;
 
-#[derive(Error, Debug, PartialEq)]
+use crate::models::SourcePosition;
+
+#[derive(Error, Debug, Clone, PartialEq)]
 pub enum EdiError {
     #[error("Invalid segment format: {0}")]
     InvalidSegmentFormat(String),
@@ -19,4 +21,247 @@ pub enum EdiError {
     
     #[error("Validation error: {0}")]
     ValidationError(String),
-}
\ No newline at end of file
+
+    #[error("I/O error: {0}")]
+    Io(String),
+
+    #[error("Cannot decode {encoding} data at byte {offset}")]
+    UndecodableData {
+        encoding: String,
+        offset: usize,
+    },
+
+    #[error("Invalid ISA field {field}: expected {expected} characters, found {actual}")]
+    InvalidIsaField {
+        field: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("Interchange control number mismatch: ISA13 is {isa13} but IEA02 is {iea02}")]
+    InterchangeControlNumberMismatch {
+        isa13: String,
+        iea02: String,
+    },
+
+    #[error("Group control number mismatch: GS06 is {gs06} but GE02 is {ge02}")]
+    GroupControlNumberMismatch {
+        gs06: String,
+        ge02: String,
+    },
+
+    #[error("Transaction control number mismatch: ST02 is {st02} but SE02 is {se02}")]
+    TransactionControlNumberMismatch {
+        st02: String,
+        se02: String,
+    },
+
+    #[error("Segment count mismatch: SE01 is {se01} but the transaction has {actual} segments")]
+    SegmentCountMismatch {
+        se01: String,
+        actual: usize,
+    },
+
+    #[error("Transaction count mismatch: GE01 is {ge01} but the group has {actual} transactions")]
+    TransactionCountMismatch {
+        ge01: String,
+        actual: usize,
+    },
+
+    #[error("Group count mismatch: IEA01 is {iea01} but the interchange has {actual} groups")]
+    GroupCountMismatch {
+        iea01: String,
+        actual: usize,
+    },
+
+    #[error("Missing {trailer} trailer for control number {control_number}")]
+    MissingTrailer {
+        trailer: String,
+        control_number: String,
+    },
+
+    #[error("Unexpected {id} segment {context}")]
+    UnexpectedSegment {
+        id: String,
+        context: String,
+    },
+
+    #[error("Segment ends with an element separator: {0}")]
+    TrailingElementSeparator(String),
+
+    #[error("Segment is {actual} characters long, over the limit of {max}")]
+    SegmentTooLong {
+        max: usize,
+        actual: usize,
+    },
+
+    #[error("Input has more than {max} segments")]
+    TooManySegments {
+        max: usize,
+    },
+
+    #[error("Mandatory element {reference} is missing")]
+    MissingElement {
+        reference: String,
+    },
+
+    #[error("{segment} has {actual} elements, more than the {max} defined")]
+    TooManyElements {
+        segment: String,
+        max: usize,
+        actual: usize,
+    },
+
+    #[error("Invalid code value {value} in {reference}")]
+    InvalidCodeValue {
+        reference: String,
+        value: String,
+    },
+
+    #[error("{reference} value {value} is not a valid {data_type}")]
+    InvalidDataType {
+        reference: String,
+        value: String,
+        data_type: String,
+    },
+
+    #[error("{reference} value {value} is shorter than the minimum length of {min}")]
+    ElementTooShort {
+        reference: String,
+        value: String,
+        min: usize,
+    },
+
+    #[error("{reference} value {value} is longer than the maximum length of {max}")]
+    ElementTooLong {
+        reference: String,
+        value: String,
+        max: usize,
+    },
+
+    #[error("{reference} value {value} is not a valid date")]
+    InvalidDate {
+        reference: String,
+        value: String,
+    },
+
+    #[error("{reference} value {value} is not a valid time")]
+    InvalidTime {
+        reference: String,
+        value: String,
+    },
+
+    #[error("Syntax note {note} violated at {reference}")]
+    SyntaxNoteViolated {
+        /// The note with its segment, such as N1P0304.
+        note: String,
+        reference: String,
+    },
+
+    #[error("{id} segment is out of order in transaction set {transaction_set}")]
+    SegmentOutOfOrder {
+        id: String,
+        transaction_set: String,
+    },
+
+    #[error("{id} segment is not defined in transaction set {transaction_set}")]
+    UndefinedSegment {
+        id: String,
+        transaction_set: String,
+    },
+
+    #[error("{id} segment exceeds its maximum use of {max_use}")]
+    SegmentMaxUseExceeded {
+        id: String,
+        max_use: usize,
+    },
+
+    #[error("{id} segment is not used in transaction set {transaction_set}")]
+    SegmentNotUsed {
+        id: String,
+        transaction_set: String,
+    },
+
+    #[error("{reference} is not used")]
+    ElementNotUsed {
+        reference: String,
+    },
+
+    #[error("{reference} is {actual} but the {total} is {expected}")]
+    TotalMismatch {
+        reference: String,
+        expected: String,
+        actual: String,
+        /// What `expected` was computed from, such as "number of PO1 line items".
+        total: String,
+    },
+
+    #[error("Loop {id} exceeds its maximum of {max_use} repetitions")]
+    LoopMaxUseExceeded {
+        id: String,
+        max_use: usize,
+    },
+
+    #[error("Invalid schema: {0}")]
+    InvalidSchema(String),
+
+    #[error("{error} at {}", describe_location(.position, .element))]
+    Located {
+        error: Box<EdiError>,
+        position: SourcePosition,
+        /// 1-based element position within the segment, when known.
+        element: Option<usize>,
+    },
+}
+
+fn describe_location(position: &SourcePosition, element: &Option<usize>) -> String {
+    match element {
+        Some(element) => format!(
+            "segment {}, element {} (line {}, column {}, byte {})",
+            position.segment_index + 1,
+            element,
+            position.line,
+            position.column,
+            position.byte_offset
+        ),
+        None => position.to_string(),
+    }
+}
+
+impl EdiError {
+    /// Attaches a source location, unless the error already carries one.
+    pub fn at(self, position: Option<SourcePosition>, element: Option<usize>) -> Self {
+        match (self, position) {
+            (located @ Self::Located { .. }, _) | (located, None) => located,
+            (error, Some(position)) => Self::Located {
+                error: Box::new(error),
+                position,
+                element,
+            },
+        }
+    }
+
+    /// The error without any location wrapper.
+    pub fn root(&self) -> &EdiError {
+        match self {
+            Self::Located { error, .. } => error.root(),
+            other => other,
+        }
+    }
+
+    /// Where in the input the error was found, if known.
+    pub fn position(&self) -> Option<SourcePosition> {
+        match self {
+            Self::Located { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// The 1-based element position the error refers to, if known.
+    pub fn element(&self) -> Option<usize> {
+        match self {
+            Self::Located { element, .. } => *element,
+            _ => None,
+        }
+    }
+}
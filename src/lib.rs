@@ -10,7 +10,7 @@
 //! - ✅ **Segment Validation**: Built-in validation for common segments (BEG, PO1, N1, DTM, etc.)
 //! - ✅ **Loop-Aware Parsing**: Structured parsing of EDI loops (party loops, line item loops)
 //! - ✅ **Extensible Architecture**: Easy to add new segments, document types, and validation rules
-//! - ✅ **Error Handling**: Comprehensive error reporting with detailed validation messages
+//! - ✅ **Error Handling**: Comprehensive error reporting with detailed validation messages and the segment, line and column of each error
 //! - ✅ **Performance**: Zero-copy parsing with efficient memory usage
 //!
 //! ## Quick Start
@@ -70,7 +70,7 @@
 //! use edi_parser::{X12Parser, EdiParser, PurchaseOrder850};
 //!
 //! # fn main() -> Result<(), Box<dyn std::error::Error>> {
-//! let edi_data = "ISA*00*          *00*          *01*SENDERID     *01*RECEIVERID   *230101*1253*U*00401*000000001*0*T*>~GS*PO*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*850*0001~BEG*00*SA*PO-001**20230101~N1*ST*ABC Corporation*92*12345~PO1*1*100*EA*10.50**BP*ITEM-001~CTT*1~SE*6*0001~GE*1*1~IEA*1*000000001~";
+//! let edi_data = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*PO*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*850*0001~BEG*00*SA*PO-001**20230101~N1*ST*ABC Corporation*92*12345~PO1*1*100*EA*10.50**BP*ITEM-001~CTT*1~SE*6*0001~GE*1*1~IEA*1*000000001~";
 //! let parser = X12Parser::default();
 //! let interchange = parser.parse(&edi_data)?;
 //!
@@ -106,16 +106,24 @@
 //!
 //! match parser.parse(&edi_data) {
 //!     Ok(interchange) => { /* Success */ }
-//!     Err(EdiError::InvalidSegmentFormat(msg)) => {
-//!         println!("Segment format error: {}", msg);
-//!     }
-//!     Err(EdiError::MissingRequiredSegment(seg)) => {
-//!         println!("Missing required segment: {}", seg);
-//!     }
-//!     Err(EdiError::ValidationError(msg)) => {
-//!         println!("Validation error: {}", msg);
+//!     Err(error) => {
+//!         // Errors found in the input carry the segment, line and column
+//!         if let Some(position) = error.position() {
+//!             println!("Error at {}", position);
+//!         }
+//!         match error.root() {
+//!             EdiError::InvalidSegmentFormat(msg) => {
+//!                 println!("Segment format error: {}", msg);
+//!             }
+//!             EdiError::MissingRequiredSegment(seg) => {
+//!                 println!("Missing required segment: {}", seg);
+//!             }
+//!             EdiError::ValidationError(msg) => {
+//!                 println!("Validation error: {}", msg);
+//!             }
+//!             _ => { /* Other errors */ }
+//!         }
 //!     }
-//!     _ => { /* Other errors */ }
 //! }
 //! # Ok(())
 //! # }
@@ -123,26 +131,41 @@
 //!
 //! ## Adding Custom Segments
 //!
-//! ### Method 1: Simple Validation
+//! ### Method 1: Custom Validators
+//! Rules the schemas cannot express are registered on a `Validator`, per
+//! segment ID or per transaction, and run alongside the built-in checks:
 //! ```rust
-//! use edi_parser::{TransactionType, Segment};
-//!
-//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
-//! // Example of custom segment validation
-//! let segment = Segment::new("MY_SEG".to_string(), vec!["A".to_string(), "B".to_string()]);
-//!
-//! match segment.id.as_str() {
-//!     "MY_SEG" => {
-//!         if segment.elements.len() < 2 {
-//!             println!("MY_SEG requires at least 2 elements");
-//!         } else {
-//!             println!("MY_SEG validation passed");
-//!         }
-//!     }
-//!     _ => println!("Unknown segment"),
-//! }
-//! # Ok(())
-//! # }
+//! use edi_parser::{
+//!     EdiError, IssueCode, IssueLocation, Segment, Transaction, ValidationReport, Validator, X12Parser,
+//! };
+//!
+//! let validator = Validator::default()
+//!     .with_segment_validator(
+//!         "850",
+//!         "MY_SEG",
+//!         |segment: &Segment, location: &IssueLocation, report: &mut ValidationReport| {
+//!             if segment.element(2).is_none() {
+//!                 report.error(
+//!                     IssueCode::InvalidSegment,
+//!                     location.clone().element(2),
+//!                     EdiError::ValidationError("MY_SEG requires at least 2 elements".to_string()),
+//!                 );
+//!             }
+//!         },
+//!     )
+//!     .with_transaction_validator(
+//!         "850",
+//!         |transaction: &Transaction, location: &IssueLocation, report: &mut ValidationReport| {
+//!             if !transaction.segments.iter().any(|segment| segment.id == "MY_SEG") {
+//!                 report.error(
+//!                     IssueCode::MissingSegment,
+//!                     location.clone(),
+//!                     EdiError::MissingRequiredSegment("MY_SEG".to_string()),
+//!                 );
+//!             }
+//!         },
+//!     );
+//! let parser = X12Parser::default().with_validator(validator);
 //! ```
 //!
 //! ### Method 2: Loop-Based Parsing
@@ -150,7 +173,7 @@
 //!
 //! ## Performance
 //!
-//! - **Zero-copy parsing** where possible
+//! - **Zero-copy parsing** with `X12Parser::parse_borrowed`, which slices segments out of the input buffer
 //! - **Efficient memory usage** with Vec-based storage
 //! - **Fast validation** with early error detection
 //! - **Scalable architecture** for large EDI files
@@ -159,8 +182,11 @@ pub mod error;
 pub mod models;
 pub mod parsers;
 pub mod utils;
+pub mod validation;
 
 // Re-export the main types for easier access
 pub use error::EdiError;
 pub use models::*;
-pub use parsers::*;
\ No newline at end of file
+pub use parsers::*;
+pub use validation::*;
+
@@ -0,0 +1,176 @@
+use super::segment::split_repetitions;
+use super::{
+    Delimiters, Element, FunctionalGroup, InterchangeControl, IsaHeader, Segment, SourcePosition,
+    Transaction, TransactionType, X12Version,
+};
+use crate::validation::ValidationIssue;
+
+/// A segment that borrows its id and elements from the input buffer.
+///
+/// Elements are sliced out of the segment text on demand, so reading a
+/// segment never allocates. Nothing is cached between calls either: each
+/// [`element`](Self::element) scans the text from the start, so code that
+/// reads most of a segment should take a single pass over
+/// [`elements`](Self::elements) instead.
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentRef<'a> {
+    pub id: &'a str,
+    raw: &'a str,
+    pub delimiters: Delimiters,
+    pub position: Option<SourcePosition>,
+    trim: bool,
+}
+
+/// Segments compare by content; where they were read from is ignored.
+impl PartialEq for SegmentRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw && self.delimiters == other.delimiters
+    }
+}
+
+impl<'a> SegmentRef<'a> {
+    /// Wraps the text of one segment, without its terminator.
+    pub fn new(raw: &'a str, delimiters: Delimiters) -> Self {
+        let raw = raw.trim();
+        let id = raw.split(delimiters.element).next().unwrap_or("").trim();
+        Self { id, raw, delimiters, position: None, trim: true }
+    }
+
+    /// Wraps segment text keeping whitespace around element values; only
+    /// line breaks around the segment are removed.
+    pub fn untrimmed(raw: &'a str, delimiters: Delimiters) -> Self {
+        let raw = raw.trim_matches(['\r', '\n']);
+        let id = raw.split(delimiters.element).next().unwrap_or("").trim();
+        Self { id, raw, delimiters, position: None, trim: false }
+    }
+
+    pub fn with_position(mut self, position: SourcePosition) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// The segment text as it appeared in the input, without its terminator.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Iterates over the elements after the segment id, trimmed of
+    /// whitespace unless the segment is [`untrimmed`](Self::untrimmed).
+    pub fn elements(&self) -> impl Iterator<Item = &'a str> + 'a {
+        let trim = self.trim;
+        self.raw
+            .split(self.delimiters.element)
+            .skip(1)
+            .map(move |element| if trim { element.trim() } else { element })
+    }
+
+    pub fn element_count(&self) -> usize {
+        self.elements().count()
+    }
+
+    /// Returns the element at X12 `position` (1-based, so `BEG03` is position 3),
+    /// scanning the elements before it.
+    pub fn element(&self, position: usize) -> Option<&'a str> {
+        position
+            .checked_sub(1)
+            .and_then(|index| self.elements().nth(index))
+    }
+
+    pub fn repetitions(&self, position: usize) -> Vec<&'a str> {
+        split_repetitions(self.element(position), &self.delimiters)
+    }
+
+    pub fn composite(&self, position: usize) -> Option<Element<'a>> {
+        self.repetitions(position)
+            .into_iter()
+            .next()
+            .map(|value| Element::new(value, self.delimiters.component))
+    }
+
+    pub fn component(&self, position: usize, component: usize) -> Option<&'a str> {
+        self.composite(position)
+            .and_then(|element| element.component(component))
+    }
+
+    pub fn to_owned(&self) -> Segment {
+        Segment {
+            id: self.id.to_string(),
+            elements: self.elements().map(str::to_string).collect(),
+            delimiters: self.delimiters,
+            position: self.position,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionRef<'a> {
+    pub segments: Vec<SegmentRef<'a>>,
+    pub transaction_set_id: &'a str,
+    pub control_number: &'a str,
+}
+
+impl<'a> TransactionRef<'a> {
+    pub fn transaction_type(&self) -> TransactionType {
+        TransactionType::from_id(self.transaction_set_id)
+    }
+
+    pub fn to_owned(&self) -> Transaction {
+        Transaction::new(
+            self.segments.iter().map(SegmentRef::to_owned).collect(),
+            self.transaction_set_id.to_string(),
+            self.control_number.to_string(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionalGroupRef<'a> {
+    pub gs_segment: SegmentRef<'a>,
+    pub ge_segment: Option<SegmentRef<'a>>,
+    pub transactions: Vec<TransactionRef<'a>>,
+}
+
+impl<'a> FunctionalGroupRef<'a> {
+    pub fn to_owned(&self) -> FunctionalGroup {
+        FunctionalGroup {
+            gs_segment: self.gs_segment.to_owned(),
+            ge_segment: self.ge_segment.as_ref().map(SegmentRef::to_owned),
+            transactions: self.transactions.iter().map(TransactionRef::to_owned).collect(),
+        }
+    }
+}
+
+/// An interchange whose segments borrow from the input buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterchangeRef<'a> {
+    /// Owned rather than borrowed: it is parsed once per interchange, its
+    /// sixteen fields are short, and the same header is handed to the owned
+    /// model and to streamed transactions, which outlive the input.
+    pub isa_header: IsaHeader,
+    /// Where the ISA was read from.
+    pub isa_position: Option<SourcePosition>,
+    pub iea_segment: Option<SegmentRef<'a>>,
+    pub functional_groups: Vec<FunctionalGroupRef<'a>>,
+    pub version: X12Version,
+    /// Envelope structure problems recovered from by a lenient parse.
+    pub envelope_issues: Vec<ValidationIssue>,
+}
+
+impl<'a> InterchangeRef<'a> {
+    /// Copies the borrowed tree into the owned `InterchangeControl` model.
+    pub fn to_owned(&self) -> InterchangeControl {
+        let mut isa_segment = self.isa_header.to_segment();
+        isa_segment.position = self.isa_position;
+        InterchangeControl {
+            isa_segment,
+            iea_segment: self.iea_segment.as_ref().map(SegmentRef::to_owned),
+            functional_groups: self
+                .functional_groups
+                .iter()
+                .map(FunctionalGroupRef::to_owned)
+                .collect(),
+            version: self.version.clone(),
+            envelope_issues: self.envelope_issues.clone(),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+/// Reads an X12 date in CCYYMMDD or YYMMDD format, rejecting dates that do
+/// not exist such as 20231399 or 20230229.
+///
+/// Two-digit years 00–68 are read as 2000–2068 and 69–99 as 1969–1999.
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    if !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    match value.len() {
+        8 => NaiveDate::parse_from_str(value, "%Y%m%d").ok(),
+        6 => NaiveDate::parse_from_str(value, "%y%m%d").ok(),
+        _ => None,
+    }
+}
+
+/// Reads an X12 time in HHMM, HHMMSS or HHMMSSd.. format, where the digits
+/// after the seconds are decimal fractions of a second.
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    if !value.chars().all(|c| c.is_ascii_digit()) || !(4..=8).contains(&value.len()) || value.len() == 5 {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| value.get(range).and_then(|digits| digits.parse::<u32>().ok());
+    let hour = number(0..2)?;
+    let minute = number(2..4)?;
+    let second = if value.len() >= 6 { number(4..6)? } else { 0 };
+    let fraction = &value[value.len().min(6)..];
+    let nanos = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u32>().ok()? * 10u32.pow(9 - fraction.len() as u32)
+    };
+    NaiveTime::from_hms_nano_opt(hour, minute, second, nanos)
+}
+
+/// A date or time period expressed with a format qualifier, as in DTM05
+/// and DTM06.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatePeriod {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    /// An inclusive range of dates.
+    DateRange(NaiveDate, NaiveDate),
+    /// An inclusive range of date-times.
+    DateTimeRange(NaiveDateTime, NaiveDateTime),
+}
+
+impl DatePeriod {
+    /// The format qualifiers `parse` understands: D6 (YYMMDD), D8
+    /// (CCYYMMDD), DT (CCYYMMDDHHMM), RD6 and RD8 (date ranges written
+    /// start-end) and RDT (a range of CCYYMMDDHHMM).
+    pub const FORMATS: &'static [&'static str] = &["D6", "D8", "DT", "RD6", "RD8", "RDT"];
+
+    /// Reads `value` in the format named by `format`. Fails for unknown
+    /// formats, values that do not match the format, dates that do not
+    /// exist and ranges that end before they start.
+    pub fn parse(format: &str, value: &str) -> Option<Self> {
+        let date = |value: &str, length: usize| Some(value).filter(|v| v.len() == length).and_then(parse_date);
+        match format {
+            "D6" => date(value, 6).map(Self::Date),
+            "D8" => date(value, 8).map(Self::Date),
+            "DT" => parse_date_time(value).map(Self::DateTime),
+            "RD6" | "RD8" => {
+                let length = if format == "RD6" { 6 } else { 8 };
+                let (start, end) = value.split_once('-')?;
+                let (start, end) = (date(start, length)?, date(end, length)?);
+                (start <= end).then_some(Self::DateRange(start, end))
+            }
+            "RDT" => {
+                let (start, end) = value.split_once('-')?;
+                let (start, end) = (parse_date_time(start)?, parse_date_time(end)?);
+                (start <= end).then_some(Self::DateTimeRange(start, end))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// CCYYMMDDHHMM.
+fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    if value.len() != 12 {
+        return None;
+    }
+    let date = parse_date(value.get(..8)?)?;
+    let time = parse_time(value.get(8..)?)?;
+    Some(date.and_time(time))
+}
@@ -0,0 +1,35 @@
+use super::IsaHeader;
+use crate::error::EdiError;
+
+/// The separators declared by an interchange's ISA header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delimiters {
+    pub element: char,
+    pub segment: char,
+    pub component: char,
+    /// ISA11 repetition separator, declared from version 00501 onwards.
+    pub repetition: Option<char>,
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Self {
+            element: '*',
+            segment: '~',
+            component: '>',
+            repetition: None,
+        }
+    }
+}
+
+impl Delimiters {
+    /// Reads the delimiters from the fixed positions of an ISA segment.
+    ///
+    /// The element separator is the fourth character of the ISA, the
+    /// component separator is ISA16 (position 105) and the segment terminator
+    /// immediately follows it (position 106). For 5010 and later interchanges
+    /// ISA11 supplies the repetition separator.
+    pub fn from_isa(input: &str) -> Result<Self, EdiError> {
+        IsaHeader::parse(input).map(|header| header.delimiters)
+    }
+}
@@ -0,0 +1,36 @@
+/// A single element occurrence, split into components on the sub-element separator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Element<'a> {
+    value: &'a str,
+    component_separator: char,
+}
+
+impl<'a> Element<'a> {
+    pub fn new(value: &'a str, component_separator: char) -> Self {
+        Self {
+            value,
+            component_separator,
+        }
+    }
+
+    /// The element text as it appeared in the segment.
+    pub fn value(&self) -> &'a str {
+        self.value
+    }
+
+    pub fn is_composite(&self) -> bool {
+        self.value.contains(self.component_separator)
+    }
+
+    /// Returns all components; a simple element has exactly one.
+    pub fn components(&self) -> Vec<&'a str> {
+        self.value.split(self.component_separator).collect()
+    }
+
+    /// Returns the component at `position` (1-based, so `SV101-2` is position 2).
+    pub fn component(&self, position: usize) -> Option<&'a str> {
+        position
+            .checked_sub(1)
+            .and_then(|index| self.value.split(self.component_separator).nth(index))
+    }
+}
@@ -1,4 +1,5 @@
-use super::{Segment, Transaction, X12Version};
+use super::{IsaHeader, Segment, Transaction, X12Version};
+use crate::validation::ValidationIssue;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct InterchangeControl {
@@ -6,6 +7,8 @@ pub struct InterchangeControl {
     pub iea_segment: Option<Segment>,
     pub functional_groups: Vec<FunctionalGroup>,
     pub version: X12Version,
+    /// Envelope structure problems recovered from by a lenient parse.
+    pub envelope_issues: Vec<ValidationIssue>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,4 +16,51 @@ pub struct FunctionalGroup {
     pub gs_segment: Segment,
     pub ge_segment: Option<Segment>,
     pub transactions: Vec<Transaction>,
-}
\ No newline at end of file
+}
+
+impl InterchangeControl {
+    /// The ISA as a fixed-width header, rebuilt from `isa_segment` so the
+    /// two cannot disagree.
+    pub fn isa_header(&self) -> IsaHeader {
+        IsaHeader::from_segment(&self.isa_segment)
+    }
+
+    /// Every segment of the interchange, ISA through IEA, in document order.
+    pub(crate) fn segments_mut(&mut self) -> Vec<&mut Segment> {
+        let mut segments = vec![&mut self.isa_segment];
+        for fg in &mut self.functional_groups {
+            segments.push(&mut fg.gs_segment);
+            for transaction in &mut fg.transactions {
+                segments.extend(transaction.segments.iter_mut());
+            }
+            segments.extend(fg.ge_segment.as_mut());
+        }
+        segments.extend(self.iea_segment.as_mut());
+        segments
+    }
+
+    /// Serializes the interchange with the delimiters declared in its ISA.
+    pub fn to_edi(&self) -> String {
+        let isa_header = self.isa_header();
+        let delimiters = &isa_header.delimiters;
+        let mut out = isa_header.to_string();
+
+        for fg in &self.functional_groups {
+            out.push_str(&fg.gs_segment.to_edi(delimiters));
+            for transaction in &fg.transactions {
+                for segment in &transaction.segments {
+                    out.push_str(&segment.to_edi(delimiters));
+                }
+            }
+            if let Some(ge) = &fg.ge_segment {
+                out.push_str(&ge.to_edi(delimiters));
+            }
+        }
+
+        if let Some(iea) = &self.iea_segment {
+            out.push_str(&iea.to_edi(delimiters));
+        }
+
+        out
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt;
+
+use super::{Delimiters, Segment};
+use crate::error::EdiError;
+
+/// Length of a well-formed ISA segment, including its segment terminator.
+pub const ISA_LENGTH: usize = 106;
+
+/// Fixed widths of ISA01 through ISA16.
+pub const ISA_FIELD_WIDTHS: [usize; 16] = [2, 10, 2, 10, 2, 15, 2, 15, 6, 4, 1, 5, 9, 1, 1, 1];
+
+/// A fixed-width ISA header that keeps every field exactly as it was sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsaHeader {
+    fields: Vec<String>,
+    pub delimiters: Delimiters,
+}
+
+impl IsaHeader {
+    /// Reads the ISA at the start of `input`, checking the width of every field.
+    pub fn parse(input: &str) -> Result<Self, EdiError> {
+        let isa = input.trim_start();
+
+        if !isa.starts_with("ISA") {
+            return Err(EdiError::InvalidSegmentFormat(
+                "Interchange must start with an ISA segment".to_string(),
+            ));
+        }
+
+        let element = isa[3..].chars().next().ok_or_else(|| {
+            EdiError::InvalidSegmentFormat("ISA segment is missing its element separator".to_string())
+        })?;
+        if element.is_ascii_alphanumeric() || element.is_whitespace() {
+            return Err(EdiError::InvalidSegmentFormat(format!(
+                "Invalid ISA element separator: {:?}",
+                element
+            )));
+        }
+
+        // ISA01..ISA15 are separated normally; ISA16 is followed directly by the terminator
+        let mut parts = isa[3 + element.len_utf8()..].splitn(16, element);
+        let mut fields = Vec::with_capacity(16);
+        for position in 1..=15 {
+            let field = parts.next().ok_or_else(|| {
+                EdiError::InvalidSegmentFormat(format!("ISA segment is missing ISA{:02}", position))
+            })?;
+            fields.push(field.to_string());
+        }
+
+        let mut tail = parts.next().unwrap_or("").chars();
+        let component = tail.next().ok_or_else(|| {
+            EdiError::InvalidSegmentFormat("ISA segment is missing ISA16".to_string())
+        })?;
+        let segment = tail.next().ok_or_else(|| {
+            EdiError::InvalidSegmentFormat("ISA segment is missing its segment terminator".to_string())
+        })?;
+        fields.push(component.to_string());
+
+        for (index, field) in fields.iter().enumerate() {
+            let expected = ISA_FIELD_WIDTHS[index];
+            let actual = field.chars().count();
+            if actual != expected {
+                return Err(EdiError::InvalidIsaField {
+                    field: format!("ISA{:02}", index + 1),
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        if segment == element || component == element || segment == component {
+            return Err(EdiError::InvalidSegmentFormat(format!(
+                "ISA delimiters must be distinct (element {:?}, segment {:?}, component {:?})",
+                element, segment, component
+            )));
+        }
+
+        let repetition = Self::repetition_separator(&fields[10], &fields[11])
+            .filter(|&r| r != element && r != segment && r != component);
+
+        Ok(Self {
+            fields,
+            delimiters: Delimiters {
+                element,
+                segment,
+                component,
+                repetition,
+            },
+        })
+    }
+
+    /// ISA11 only carries a repetition separator from version 00501 onwards;
+    /// earlier versions use it for the standards identifier (`U`).
+    fn repetition_separator(isa11: &str, isa12: &str) -> Option<char> {
+        let version: u32 = isa12.parse().ok()?;
+        let separator = isa11.chars().next()?;
+        if version >= 501 && !separator.is_ascii_alphanumeric() && !separator.is_whitespace() {
+            Some(separator)
+        } else {
+            None
+        }
+    }
+
+    /// Returns ISA`position` (1-based) exactly as sent, including its padding.
+    pub fn raw(&self, position: usize) -> Option<&str> {
+        position
+            .checked_sub(1)
+            .and_then(|index| self.fields.get(index))
+            .map(|s| s.as_str())
+    }
+
+    /// Returns ISA`position` (1-based) with its padding removed.
+    pub fn value(&self, position: usize) -> Option<&str> {
+        self.raw(position).map(|s| s.trim())
+    }
+
+    pub fn sender_qualifier(&self) -> &str {
+        self.fields[4].trim()
+    }
+
+    pub fn sender_id(&self) -> &str {
+        self.fields[5].trim()
+    }
+
+    pub fn receiver_qualifier(&self) -> &str {
+        self.fields[6].trim()
+    }
+
+    pub fn receiver_id(&self) -> &str {
+        self.fields[7].trim()
+    }
+
+    pub fn version(&self) -> &str {
+        &self.fields[11]
+    }
+
+    pub fn control_number(&self) -> &str {
+        &self.fields[12]
+    }
+
+    pub fn usage_indicator(&self) -> &str {
+        &self.fields[14]
+    }
+
+    /// Rebuilds a header from an ISA segment with trimmed element values,
+    /// padding each field back to its fixed width with spaces.
+    pub fn from_segment(segment: &Segment) -> Self {
+        let fields = ISA_FIELD_WIDTHS
+            .iter()
+            .enumerate()
+            .map(|(index, &width)| {
+                let value = segment.elements.get(index).map(String::as_str).unwrap_or_default();
+                format!("{:<width$}", value, width = width)
+            })
+            .collect();
+        Self { fields, delimiters: segment.delimiters }
+    }
+
+    /// Converts the header into a segment with trimmed element values.
+    pub fn to_segment(&self) -> Segment {
+        Segment::with_delimiters(
+            "ISA".to_string(),
+            self.fields.iter().map(|f| f.trim().to_string()).collect(),
+            self.delimiters,
+        )
+    }
+}
+
+impl fmt::Display for IsaHeader {
+    /// Re-emits the header byte for byte, including the segment terminator.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ISA")?;
+        for field in &self.fields[..15] {
+            write!(f, "{}{}", self.delimiters.element, field)?;
+        }
+        write!(
+            f,
+            "{}{}{}",
+            self.delimiters.element, self.delimiters.component, self.delimiters.segment
+        )
+    }
+}
@@ -120,7 +120,7 @@ impl PurchaseOrder850 {
         }
 
         // Add remaining summary segments
-        while let Some(segment) = segments.next() {
+        for segment in segments {
             summary_segments.push(segment.clone());
         }
 
@@ -149,7 +149,7 @@ impl PurchaseOrder850 {
     pub fn get_parties_by_type(&self, entity_type: &str) -> Vec<&PartyLoop> {
         self.party_loops.iter()
             .filter(|party| {
-                party.n1_segment.elements.get(0)
+                party.n1_segment.elements.first()
                     .map(|et| et == entity_type)
                     .unwrap_or(false)
             })
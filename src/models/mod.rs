@@ -1,11 +1,23 @@
 pub mod segment;
+pub mod element;
 pub mod transaction;
 pub mod interchange;
 pub mod version;
 pub mod loops;
+pub mod delimiters;
+pub mod isa;
+pub mod borrowed;
+pub mod position;
+pub mod datetime;
 
 pub use segment::Segment;
+pub use element::Element;
 pub use transaction::{Transaction, TransactionType};
 pub use interchange::*;
 pub use version::X12Version;
-pub use loops::*;
\ No newline at end of file
+pub use loops::*;
+pub use delimiters::Delimiters;
+pub use isa::IsaHeader;
+pub use position::SourcePosition;
+pub use datetime::{parse_date, parse_time, DatePeriod};
+pub use borrowed::{SegmentRef, TransactionRef, FunctionalGroupRef, InterchangeRef};
\ No newline at end of file
@@ -0,0 +1,29 @@
+use std::fmt;
+
+/// Where a segment was found in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SourcePosition {
+    /// Zero-based index of the segment in the input.
+    pub segment_index: usize,
+    /// Byte offset of the first character of the segment.
+    pub byte_offset: usize,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for SourcePosition {
+    /// Segments are numbered from 1 in messages, matching how support staff
+    /// count them in a file.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "segment {} (line {}, column {}, byte {})",
+            self.segment_index + 1,
+            self.line,
+            self.column,
+            self.byte_offset
+        )
+    }
+}
@@ -1,11 +1,137 @@
-#[derive(Debug, Clone, PartialEq)]
+use chrono::{NaiveDate, NaiveTime};
+
+use super::{datetime, DatePeriod, Delimiters, Element, SourcePosition};
+
+#[derive(Debug, Clone)]
 pub struct Segment {
     pub id: String,
     pub elements: Vec<String>,
+    /// Separators of the interchange this segment was read from.
+    pub delimiters: Delimiters,
+    /// Where the segment was read from; `None` for segments built in code.
+    pub position: Option<SourcePosition>,
+}
+
+/// Segments compare by content; where they were read from is ignored.
+impl PartialEq for Segment {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.elements == other.elements && self.delimiters == other.delimiters
+    }
 }
 
 impl Segment {
     pub fn new(id: String, elements: Vec<String>) -> Self {
-        Self { id, elements }
+        Self::with_delimiters(id, elements, Delimiters::default())
+    }
+
+    pub fn with_delimiters(id: String, elements: Vec<String>, delimiters: Delimiters) -> Self {
+        Self { id, elements, delimiters, position: None }
+    }
+
+    pub fn with_position(mut self, position: SourcePosition) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    /// Returns the element at X12 `position` (1-based, so `BEG03` is position 3).
+    pub fn element(&self, position: usize) -> Option<&str> {
+        position
+            .checked_sub(1)
+            .and_then(|index| self.elements.get(index))
+            .map(|s| s.as_str())
+    }
+
+    /// Returns every occurrence of a repeating element.
+    ///
+    /// Elements without repetitions yield a single occurrence, and missing or
+    /// empty elements yield none.
+    pub fn repetitions(&self, position: usize) -> Vec<&str> {
+        split_repetitions(self.element(position), &self.delimiters)
+    }
+
+    /// Returns the given occurrence (1-based) of a repeating element.
+    pub fn repetition(&self, position: usize, occurrence: usize) -> Option<&str> {
+        occurrence
+            .checked_sub(1)
+            .and_then(|index| self.repetitions(position).get(index).copied())
+    }
+
+    /// Returns the element at `position` as a composite, using the first
+    /// occurrence when the element repeats.
+    pub fn composite(&self, position: usize) -> Option<Element<'_>> {
+        self.occurrences(position).into_iter().next()
+    }
+
+    /// Returns every occurrence of an element as a composite.
+    pub fn occurrences(&self, position: usize) -> Vec<Element<'_>> {
+        self.repetitions(position)
+            .into_iter()
+            .map(|value| Element::new(value, self.delimiters.component))
+            .collect()
+    }
+
+    /// Returns the components of the element at `position`.
+    pub fn components(&self, position: usize) -> Vec<&str> {
+        self.composite(position)
+            .map(|element| element.components())
+            .unwrap_or_default()
+    }
+
+    /// Returns a single component, e.g. `component(1, 2)` for `SV101-2`.
+    pub fn component(&self, position: usize, component: usize) -> Option<&str> {
+        self.composite(position)
+            .and_then(|element| element.component(component))
+    }
+
+    /// Returns the element at `position` as a date, from CCYYMMDD or
+    /// YYMMDD. `None` when the element is missing or not a valid date.
+    pub fn date(&self, position: usize) -> Option<NaiveDate> {
+        self.element(position).and_then(datetime::parse_date)
+    }
+
+    /// Returns the element at `position` as a time, from HHMM[SS[d..]].
+    pub fn time(&self, position: usize) -> Option<NaiveTime> {
+        self.element(position).and_then(datetime::parse_time)
     }
-}
\ No newline at end of file
+
+    /// Returns the element at `value_position` read in the format given by
+    /// the element at `format_position`, e.g. `date_period(5, 6)` for a DTM
+    /// with `DTM*007****RD8*20230101-20230131`.
+    pub fn date_period(&self, format_position: usize, value_position: usize) -> Option<DatePeriod> {
+        DatePeriod::parse(self.element(format_position)?, self.element(value_position)?)
+    }
+
+    /// Serializes the segment, re-joining repetitions and components with the
+    /// separators of the target interchange.
+    pub fn to_edi(&self, delimiters: &Delimiters) -> String {
+        let component = delimiters.component.to_string();
+        let repetition = delimiters
+            .repetition
+            .or(self.delimiters.repetition)
+            .map(|r| r.to_string())
+            .unwrap_or_default();
+
+        let mut out = self.id.clone();
+        for position in 1..=self.elements.len() {
+            out.push(delimiters.element);
+            let occurrences: Vec<String> = self
+                .occurrences(position)
+                .iter()
+                .map(|element| element.components().join(&component))
+                .collect();
+            out.push_str(&occurrences.join(&repetition));
+        }
+        out.push(delimiters.segment);
+        out
+    }
+}
+
+pub(crate) fn split_repetitions<'a>(value: Option<&'a str>, delimiters: &Delimiters) -> Vec<&'a str> {
+    match value {
+        None | Some("") => Vec::new(),
+        Some(value) => match delimiters.repetition {
+            Some(separator) => value.split(separator).collect(),
+            None => vec![value],
+        },
+    }
+}
@@ -1,4 +1,5 @@
 use super::Segment;
+use crate::validation::{validate_segment, CodeListRegistry, IssueLocation, TransactionSetSchema, ValidationReport};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum TransactionType {
@@ -16,134 +17,32 @@ impl TransactionType {
         }
     }
 
+    /// The built-in schema for this transaction set, if the crate ships one.
+    pub fn schema(&self) -> Option<&'static TransactionSetSchema> {
+        TransactionSetSchema::builtin(self.as_str())
+    }
+
     pub fn required_segments(&self) -> Vec<&str> {
-        match self {
-            Self::Invoice810 => vec!["ST", "BIG", "SE"],
-            Self::PurchaseOrder850 => vec!["ST", "BEG", "SE"], // Core required
-            Self::Unknown(_) => vec!["ST", "SE"],
+        match self.schema() {
+            Some(schema) => schema.mandatory_segments(),
+            None => vec!["ST", "SE"],
         }
     }
 
     pub fn optional_segments(&self) -> Vec<&str> {
-        match self {
-            Self::Invoice810 => vec!["N1", "IT1", "TDS", "CTT"],
-            Self::PurchaseOrder850 => vec!["N1", "PO1", "CTT", "DTM", "REF", "PER", "FOB", "ITD", "PID", "SAC"],
-            Self::Unknown(_) => vec![],
-        }
+        self.schema().map(TransactionSetSchema::optional_segments).unwrap_or_default()
     }
 
+    /// Checks `segment` against the built-in schema, failing with the first
+    /// problem found.
     pub fn validate_segment(&self, segment: &Segment) -> Result<(), String> {
-        match self {
-            Self::PurchaseOrder850 => self.validate_850_segment(segment),
-            Self::Invoice810 => self.validate_810_segment(segment),
-            Self::Unknown(_) => Ok(()), // No specific validation for unknown types
-        }
-    }
-
-    fn validate_850_segment(&self, segment: &Segment) -> Result<(), String> {
-        match segment.id.as_str() {
-            "BEG" => {
-                // BEG: Beginning Segment for Purchase Order
-                // BEG01: Transaction Set Purpose Code (00=Original, 01=Cancellation, etc.)
-                // BEG02: Purchase Order Type Code (SA=Stand-alone order)
-                // BEG03: Purchase Order Number
-                if segment.elements.len() < 3 {
-                    return Err("BEG segment requires at least 3 elements".to_string());
-                }
-                let purpose_code = &segment.elements[0];
-                if !["00", "01", "04", "05", "06", "07"].contains(&purpose_code.as_str()) {
-                    return Err(format!("Invalid BEG01 purpose code: {}", purpose_code));
-                }
-                Ok(())
-            }
-            "PO1" => {
-                // PO1: Purchase Order Line Item
-                // PO101: Assigned Identification
-                // PO102: Quantity Ordered
-                // PO103: Unit of Measure
-                // PO104: Unit Price
-                if segment.elements.len() < 4 {
-                    return Err("PO1 segment requires at least 4 elements".to_string());
-                }
-                // Validate quantity is numeric
-                if let Ok(_) = segment.elements[1].parse::<f64>() {
-                    // Valid number
-                } else {
-                    return Err(format!("PO102 quantity must be numeric: {}", segment.elements[1]));
-                }
-                Ok(())
-            }
-            "N1" => {
-                // N1: Name
-                // N101: Entity Identifier Code (ST=Ship To, BT=Bill To, etc.)
-                // N102: Name
-                if segment.elements.len() < 2 {
-                    return Err("N1 segment requires at least 2 elements".to_string());
-                }
-                let entity_code = &segment.elements[0];
-                if !["ST", "BT", "SF", "BS", "BY", "SE", "SU"].contains(&entity_code.as_str()) {
-                    return Err(format!("Invalid N101 entity code: {}", entity_code));
-                }
-                Ok(())
-            }
-            "DTM" => {
-                // DTM: Date/Time Reference
-                // DTM01: Date/Time Qualifier (002=Delivery Requested, 010=Requested Ship, etc.)
-                // DTM02: Date (CCYYMMDD format)
-                // DTM03: Time (HHMM format, optional)
-                if segment.elements.is_empty() {
-                    return Err("DTM segment requires at least 1 element".to_string());
-                }
-
-                let qualifier = &segment.elements[0];
-                let valid_qualifiers = [
-                    "002", "010", "011", "017", "035", "036", "037", "038", "063", "064",
-                    "069", "070", "071", "072", "073", "074", "075", "076", "077", "078"
-                ];
-
-                if !valid_qualifiers.contains(&qualifier.as_str()) {
-                    return Err(format!("Invalid DTM01 qualifier: {} (valid: delivery dates, ship dates, etc.)", qualifier));
-                }
-
-                // Validate date format if present
-                if segment.elements.len() > 1 {
-                    let date = &segment.elements[1];
-                    if date.len() == 8 && date.chars().all(|c| c.is_numeric()) {
-                        // Valid CCYYMMDD format
-                    } else if !date.is_empty() {
-                        return Err(format!("Invalid date format: {} (expected CCYYMMDD)", date));
-                    }
-                }
-
-                // Validate time format if present
-                if segment.elements.len() > 2 {
-                    let time = &segment.elements[2];
-                    if time.len() == 4 && time.chars().all(|c| c.is_numeric()) {
-                        // Valid HHMM format
-                    } else if !time.is_empty() {
-                        return Err(format!("Invalid time format: {} (expected HHMM)", time));
-                    }
-                }
-
-                Ok(())
-            }
-            _ => Ok(()), // Other segments don't have specific validation yet
-        }
-    }
-
-    fn validate_810_segment(&self, segment: &Segment) -> Result<(), String> {
-        match segment.id.as_str() {
-            "BIG" => {
-                // BIG: Beginning Segment for Invoice
-                // BIG01: Date
-                // BIG02: Invoice Number
-                if segment.elements.len() < 2 {
-                    return Err("BIG segment requires at least 2 elements".to_string());
-                }
-                Ok(())
-            }
-            _ => Ok(()),
-        }
+        let Some(schema) = self.schema() else {
+            return Ok(()); // No specific validation for unknown types
+        };
+        let mut report = ValidationReport::new();
+        let code_lists = CodeListRegistry::default();
+        validate_segment(schema, &code_lists, None, segment, &IssueLocation::default(), &mut report);
+        report.into_result().map_err(|error| error.to_string())
     }
 
     pub fn as_str(&self) -> &str {
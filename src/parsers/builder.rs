@@ -0,0 +1,268 @@
+use super::{EventKind, ParseMode, ParserOptions, X12Event};
+use crate::{
+    error::EdiError,
+    models::{FunctionalGroupRef, InterchangeRef, IsaHeader, SegmentRef, TransactionRef, X12Version},
+    validation::{IssueCode, IssueLocation, Severity, ValidationIssue},
+};
+
+/// Assembles interchanges from parse events as an envelope state machine.
+///
+/// The open interchange, group and transaction form the state. Every event
+/// is checked against it: a header must not arrive while its own level is
+/// still open, a trailer must close an open level, and data segments must
+/// sit inside a transaction. In strict mode the first violation is an
+/// error; in lenient mode it is recorded on the interchange and the builder
+/// recovers by closing whatever the event implies.
+pub(crate) struct TreeBuilder<'a> {
+    options: ParserOptions,
+    interchanges: Vec<InterchangeRef<'a>>,
+    current_interchange: Option<InterchangeRef<'a>>,
+    current_fg: Option<FunctionalGroupRef<'a>>,
+    current_transaction: Option<TransactionRef<'a>>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    pub(crate) fn new(options: ParserOptions) -> Self {
+        Self {
+            options,
+            interchanges: Vec::new(),
+            current_interchange: None,
+            current_fg: None,
+            current_transaction: None,
+        }
+    }
+
+    pub(crate) fn start_interchange(
+        &mut self,
+        isa_header: IsaHeader,
+        isa: SegmentRef<'a>,
+    ) -> Result<(), EdiError> {
+        self.finish_group(Some(&isa))?;
+        if let Some(interchange) = &self.current_interchange {
+            let context = format!("before IEA of interchange {}", interchange.isa_header.control_number());
+            self.unexpected(&isa, context)?;
+        }
+        self.finish_interchange();
+
+        let version = X12Version::from_isa(&isa_header.to_segment())
+            .map_err(|e| e.at(isa.position, Some(12)))?;
+        self.current_interchange = Some(InterchangeRef {
+            isa_header,
+            isa_position: isa.position,
+            iea_segment: None,
+            functional_groups: Vec::new(),
+            version,
+            envelope_issues: Vec::new(),
+        });
+        Ok(())
+    }
+
+    pub(crate) fn push(&mut self, event: X12Event<'a>) -> Result<(), EdiError> {
+        let segment = event.segment;
+        if !self.options.allow_trailing_separator && segment.raw().ends_with(segment.delimiters.element) {
+            let error = EdiError::TrailingElementSeparator(segment.raw().to_string());
+            let location = IssueLocation { element: Some(segment.element_count()), ..self.location(&segment) };
+            self.record(error, location)?;
+        }
+        match event.kind {
+            EventKind::StartGroup => {
+                self.finish_transaction(Some(&segment))?;
+                if let Some(fg) = &self.current_fg {
+                    let context = format!("before GE of group {}", group_control_number(fg));
+                    self.unexpected(&segment, context)?;
+                }
+                self.finish_group(None)?;
+                self.current_fg = Some(FunctionalGroupRef {
+                    gs_segment: segment,
+                    ge_segment: None,
+                    transactions: Vec::new(),
+                });
+            }
+            EventKind::EndGroup => {
+                if self.current_fg.is_none() {
+                    return self.unexpected(&segment, "with no open functional group".to_string());
+                }
+                self.finish_transaction(Some(&segment))?;
+                if let Some(fg) = self.current_fg.as_mut() {
+                    fg.ge_segment = Some(segment);
+                }
+                self.finish_group(None)?;
+            }
+            EventKind::StartTransaction => {
+                if let Some(transaction) = &self.current_transaction {
+                    let context = format!("before SE of transaction {}", transaction.control_number);
+                    self.unexpected(&segment, context)?;
+                    self.finish_transaction(None)?;
+                }
+                if self.current_fg.is_none() {
+                    self.unexpected(&segment, "outside any functional group".to_string())?;
+                }
+
+                let missing = |element| {
+                    EdiError::InvalidSegmentFormat(segment.raw().to_string())
+                        .at(segment.position, Some(element))
+                };
+                let transaction_set_id = segment.element(1).ok_or_else(|| missing(1))?;
+                let control_number = segment.element(2).ok_or_else(|| missing(2))?;
+
+                self.current_transaction = Some(TransactionRef {
+                    segments: vec![segment],
+                    transaction_set_id,
+                    control_number,
+                });
+            }
+            EventKind::EndTransaction => match self.current_transaction.as_mut() {
+                Some(transaction) => {
+                    transaction.segments.push(segment);
+                    self.finish_transaction(None)?;
+                }
+                None => self.unexpected(&segment, "with no open transaction".to_string())?,
+            },
+            EventKind::EndInterchange => {
+                self.finish_group(Some(&segment))?;
+                if let Some(interchange) = self.current_interchange.as_mut() {
+                    interchange.iea_segment = Some(segment);
+                }
+                self.finish_interchange();
+            }
+            EventKind::Segment => match self.current_transaction.as_mut() {
+                Some(transaction) => transaction.segments.push(segment),
+                None => self.unexpected(&segment, "outside any transaction".to_string())?,
+            },
+            // Interchanges are opened through `start_interchange`, which
+            // also needs the parsed ISA header
+            EventKind::StartInterchange => {}
+        }
+        Ok(())
+    }
+
+    /// Closes the open transaction. `found` is the segment that forced the
+    /// close when it was not the transaction's own SE.
+    fn finish_transaction(&mut self, found: Option<&SegmentRef<'a>>) -> Result<(), EdiError> {
+        if let (Some(found), Some(transaction)) = (found, &self.current_transaction) {
+            let error = missing_trailer("SE", transaction.control_number);
+            self.record(error, self.location(found))?;
+        }
+        if let Some(transaction) = self.current_transaction.take() {
+            // A transaction opened outside any group has already been reported
+            if let Some(fg) = self.current_fg.as_mut() {
+                fg.transactions.push(transaction);
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes the open group and its open transaction. `found` is the
+    /// segment that forced the close when it was not the group's GE.
+    fn finish_group(&mut self, found: Option<&SegmentRef<'a>>) -> Result<(), EdiError> {
+        self.finish_transaction(found)?;
+        if let (Some(found), Some(fg)) = (found, &self.current_fg) {
+            let error = missing_trailer("GE", group_control_number(fg));
+            self.record(error, self.location(found))?;
+        }
+        if let Some(fg) = self.current_fg.take() {
+            if let Some(interchange) = self.current_interchange.as_mut() {
+                interchange.functional_groups.push(fg);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish_interchange(&mut self) {
+        if let Some(interchange) = self.current_interchange.take() {
+            self.interchanges.push(interchange);
+        }
+    }
+
+    /// Closes everything still open at the end of the input, reporting each
+    /// missing trailer at the header it should have closed.
+    pub(crate) fn finish(mut self) -> Result<Vec<InterchangeRef<'a>>, EdiError> {
+        if let Some(transaction) = &self.current_transaction {
+            let st = transaction.segments[0];
+            let error = missing_trailer("SE", transaction.control_number);
+            let location = IssueLocation { segment_index: Some(0), ..self.location(&st) };
+            self.record(error, location)?;
+        }
+        self.finish_transaction(None)?;
+
+        if let Some(fg) = &self.current_fg {
+            let gs = fg.gs_segment;
+            let error = missing_trailer("GE", group_control_number(fg));
+            self.record(error, self.location(&gs))?;
+        }
+        self.finish_group(None)?;
+
+        if let Some(interchange) = &self.current_interchange {
+            let error = missing_trailer("IEA", interchange.isa_header.control_number());
+            let location = IssueLocation {
+                segment_id: Some("ISA".to_string()),
+                position: interchange.isa_position,
+                ..IssueLocation::default()
+            };
+            self.record(error, location)?;
+        }
+        self.finish_interchange();
+
+        Ok(self.interchanges)
+    }
+
+    /// Reports `segment` as not allowed where it appears.
+    fn unexpected(&mut self, segment: &SegmentRef<'a>, context: String) -> Result<(), EdiError> {
+        let error = EdiError::UnexpectedSegment { id: segment.id.to_string(), context };
+        self.record(error, self.location(segment))
+    }
+
+    /// Where `segment` sits relative to the open group and transaction.
+    fn location(&self, segment: &SegmentRef<'a>) -> IssueLocation {
+        let fg = self.current_fg.as_ref();
+        let transaction = self.current_transaction.as_ref();
+        IssueLocation {
+            group_index: fg.and_then(|_| {
+                self.current_interchange.as_ref().map(|i| i.functional_groups.len())
+            }),
+            group_control_number: fg.map(|fg| group_control_number(fg).to_string()),
+            // The open transaction joins its group when it closes
+            transaction_index: transaction.and(fg).map(|fg| fg.transactions.len()),
+            transaction_set_id: transaction.map(|t| t.transaction_set_id.to_string()),
+            transaction_control_number: transaction.map(|t| t.control_number.to_string()),
+            segment_id: Some(segment.id.to_string()),
+            segment_index: transaction.map(|t| t.segments.len()),
+            element: None,
+            position: segment.position,
+        }
+    }
+
+    /// Fails in strict mode; records the issue on the open interchange in
+    /// lenient mode, or on the last one for segments after its IEA.
+    fn record(&mut self, error: EdiError, location: IssueLocation) -> Result<(), EdiError> {
+        let code = match error {
+            EdiError::MissingTrailer { .. } => IssueCode::MissingTrailer,
+            EdiError::TrailingElementSeparator(_) => IssueCode::TrailingSeparator,
+            _ => IssueCode::UnexpectedSegment,
+        };
+        let interchange = self.current_interchange.as_mut().or(self.interchanges.last_mut());
+        match (self.options.mode, interchange) {
+            (ParseMode::Lenient, Some(interchange)) => {
+                interchange.envelope_issues.push(ValidationIssue {
+                    severity: Severity::Error,
+                    code,
+                    location,
+                    error,
+                });
+                Ok(())
+            }
+            _ => Err(error.at(location.position, location.element)),
+        }
+    }
+}
+
+fn group_control_number<'a>(fg: &FunctionalGroupRef<'a>) -> &'a str {
+    fg.gs_segment.element(6).unwrap_or_default()
+}
+
+fn missing_trailer(trailer: &str, control_number: &str) -> EdiError {
+    EdiError::MissingTrailer {
+        trailer: trailer.to_string(),
+        control_number: control_number.to_string(),
+    }
+}
@@ -0,0 +1,203 @@
+use std::borrow::Cow;
+
+use super::{common::LineCounter, Encoding};
+use crate::{
+    models::{Delimiters, IsaHeader, Segment, SourcePosition},
+    models::isa::ISA_LENGTH,
+    error::EdiError,
+};
+
+/// A segment sliced out of raw input bytes whose elements are decoded on demand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByteSegment<'a> {
+    bytes: &'a [u8],
+    position: SourcePosition,
+    element_separator: u8,
+    pub delimiters: Delimiters,
+    pub encoding: Encoding,
+}
+
+impl<'a> ByteSegment<'a> {
+    /// The undecoded segment bytes, without the terminator.
+    pub fn raw(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Byte offset of the segment in the input.
+    pub fn offset(&self) -> usize {
+        self.position.byte_offset
+    }
+
+    /// Where the segment starts in the input.
+    pub fn position(&self) -> SourcePosition {
+        self.position
+    }
+
+    /// Splits the segment into undecoded elements, each with its offset.
+    fn raw_elements(&self) -> impl Iterator<Item = (usize, &'a [u8])> + 'a {
+        let bytes = self.bytes;
+        let base = self.position.byte_offset;
+        let separator = self.element_separator;
+        let mut start = 0;
+        bytes.split(move |&b| b == separator).map(move |element| {
+            let offset = base + start;
+            start += element.len() + 1;
+            (offset, element)
+        })
+    }
+
+    fn decode(&self, (offset, bytes): (usize, &'a [u8])) -> Result<Cow<'a, str>, EdiError> {
+        Ok(match self.encoding.decode(bytes, offset)? {
+            Cow::Borrowed(s) => Cow::Borrowed(s.trim()),
+            Cow::Owned(s) => Cow::Owned(s.trim().to_string()),
+        })
+    }
+
+    pub fn id(&self) -> Result<Cow<'a, str>, EdiError> {
+        match self.raw_elements().next() {
+            Some(element) => self.decode(element).map_err(|e| e.at(Some(self.position), None)),
+            None => Ok(Cow::Borrowed("")),
+        }
+    }
+
+    /// Decodes the element at X12 `position` (1-based).
+    pub fn element(&self, position: usize) -> Result<Option<Cow<'a, str>>, EdiError> {
+        if position == 0 {
+            return Ok(None);
+        }
+        self.raw_elements()
+            .nth(position)
+            .map(|element| self.decode(element))
+            .transpose()
+            .map_err(|e| e.at(Some(self.position), Some(position)))
+    }
+
+    /// Decodes every element into an owned `Segment`.
+    pub fn to_owned(&self) -> Result<Segment, EdiError> {
+        let mut values = self.raw_elements().enumerate().map(|(index, element)| {
+            self.decode(element)
+                .map(Cow::into_owned)
+                .map_err(|e| e.at(Some(self.position), (index > 0).then_some(index)))
+        });
+        let id = values.next().transpose()?.unwrap_or_default();
+        let elements = values.collect::<Result<Vec<_>, _>>()?;
+        Ok(Segment::with_delimiters(id, elements, self.delimiters).with_position(self.position))
+    }
+}
+
+/// Splits raw input bytes into segments using the delimiters declared by
+/// each interchange's ISA, decoding nothing beyond the ISA itself.
+pub struct X12ByteReader<'a> {
+    input: &'a [u8],
+    encoding: Encoding,
+    counter: LineCounter,
+    segment_index: usize,
+    current: Option<(Delimiters, u8, u8)>,
+    finished: bool,
+}
+
+impl<'a> X12ByteReader<'a> {
+    pub fn new(input: &'a [u8], encoding: Encoding) -> Self {
+        Self {
+            input,
+            encoding,
+            counter: LineCounter::default(),
+            segment_index: 0,
+            current: None,
+            finished: false,
+        }
+    }
+
+    fn read_isa(&self, start: usize) -> Result<(Delimiters, u8, u8), EdiError> {
+        let end = (start + ISA_LENGTH).min(self.input.len());
+        let isa = self.encoding.decode(&self.input[start..end], start)?;
+        let delimiters = IsaHeader::parse(&isa)?.delimiters;
+
+        let encode = |delimiter: char| {
+            self.encoding.encode_delimiter(delimiter).ok_or_else(|| {
+                EdiError::InvalidSegmentFormat(format!(
+                    "Delimiter {:?} has no single-byte {} encoding",
+                    delimiter,
+                    self.encoding.name()
+                ))
+            })
+        };
+        Ok((delimiters, encode(delimiters.segment)?, encode(delimiters.element)?))
+    }
+
+    fn starts_with_isa(&self, start: usize) -> bool {
+        self.input.len() >= start + 3
+            && self.encoding.decode(&self.input[start..start + 3], start).ok().as_deref() == Some("ISA")
+    }
+
+    /// Moves the cursor over `bytes`, which must start at the cursor.
+    fn skip(&mut self, bytes: &[u8]) {
+        let text = self.encoding.decode_lossy(bytes);
+        self.counter.advance(text.chars(), bytes.len());
+    }
+
+    fn next_segment(&mut self) -> Result<Option<ByteSegment<'a>>, EdiError> {
+        let input = self.input;
+        loop {
+            let offset = self.counter.byte_offset();
+            let start = match (offset..input.len())
+                .find(|&i| !self.encoding.is_whitespace(input[i]))
+            {
+                Some(start) => start,
+                None => {
+                    self.skip(&input[offset..]);
+                    return Ok(None);
+                }
+            };
+            self.skip(&input[offset..start]);
+            let position = self.counter.position(self.segment_index);
+
+            let (delimiters, terminator, element_separator) = match self.current {
+                Some(current) if !self.starts_with_isa(start) => current,
+                _ => self.read_isa(start).map_err(|e| e.at(Some(position), None))?,
+            };
+            self.current = Some((delimiters, terminator, element_separator));
+
+            let rest = &input[start..];
+            let (bytes, consumed) = match rest.iter().position(|&b| b == terminator) {
+                Some(end) => (&rest[..end], &rest[..end + 1]),
+                None => (rest, rest),
+            };
+            self.skip(consumed);
+            if bytes.is_empty() {
+                continue;
+            }
+
+            self.segment_index += 1;
+            let segment = ByteSegment {
+                bytes,
+                position,
+                element_separator,
+                delimiters,
+                encoding: self.encoding,
+            };
+            if segment.id()? == "IEA" {
+                // The next interchange may declare different delimiters
+                self.current = None;
+            }
+            return Ok(Some(segment));
+        }
+    }
+}
+
+impl<'a> Iterator for X12ByteReader<'a> {
+    type Item = Result<ByteSegment<'a>, EdiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.next_segment().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            // Stop after the end of input or the first error
+            self.finished = true;
+        }
+        result
+    }
+}
@@ -0,0 +1,47 @@
+use crate::models::SourcePosition;
+
+/// Tracks line and column numbers while a reader scans forward through input.
+#[derive(Debug, Clone)]
+pub(crate) struct LineCounter {
+    byte_offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Default for LineCounter {
+    fn default() -> Self {
+        Self {
+            byte_offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl LineCounter {
+    /// Advances past `bytes` bytes of input that decode to `chars`.
+    pub(crate) fn advance(&mut self, chars: impl Iterator<Item = char>, bytes: usize) {
+        for c in chars {
+            if c == '\n' || c == '\u{85}' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.byte_offset += bytes;
+    }
+
+    pub(crate) fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
+    pub(crate) fn position(&self, segment_index: usize) -> SourcePosition {
+        SourcePosition {
+            segment_index,
+            byte_offset: self.byte_offset,
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::borrow::Cow;
+
+use crate::error::EdiError;
+
+/// Character encoding of raw interchange bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// ISO-8859-1; every byte maps to the code point of the same value.
+    Latin1,
+    Windows1252,
+    /// EBCDIC code page 037, as produced by IBM mainframes.
+    Ebcdic,
+}
+
+impl Encoding {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Latin1 => "ISO-8859-1",
+            Self::Windows1252 => "Windows-1252",
+            Self::Ebcdic => "EBCDIC (CP037)",
+        }
+    }
+
+    /// Decodes `bytes`, which start at byte `offset` of the input.
+    ///
+    /// ASCII data in ASCII-compatible encodings is borrowed without copying.
+    /// Undecodable bytes are reported with their absolute offset.
+    pub fn decode<'a>(&self, bytes: &'a [u8], offset: usize) -> Result<Cow<'a, str>, EdiError> {
+        match self {
+            Self::Utf8 => std::str::from_utf8(bytes)
+                .map(Cow::Borrowed)
+                .map_err(|e| self.undecodable(offset + e.valid_up_to())),
+            Self::Latin1 | Self::Windows1252 if bytes.is_ascii() => {
+                Ok(Cow::Borrowed(std::str::from_utf8(bytes).unwrap_or_default()))
+            }
+            _ => bytes
+                .iter()
+                .enumerate()
+                .map(|(index, &byte)| {
+                    self.decode_byte(byte)
+                        .ok_or_else(|| self.undecodable(offset + index))
+                })
+                .collect::<Result<String, _>>()
+                .map(Cow::Owned),
+        }
+    }
+
+    /// Decodes `bytes`, replacing anything undecodable with U+FFFD.
+    pub(crate) fn decode_lossy<'a>(&self, bytes: &'a [u8]) -> Cow<'a, str> {
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(bytes),
+            _ => match self.decode(bytes, 0) {
+                Ok(text) => text,
+                Err(_) => Cow::Owned(
+                    bytes
+                        .iter()
+                        .map(|&byte| self.decode_byte(byte).unwrap_or(char::REPLACEMENT_CHARACTER))
+                        .collect(),
+                ),
+            },
+        }
+    }
+
+    /// Decodes a single byte of a single-byte encoding.
+    fn decode_byte(&self, byte: u8) -> Option<char> {
+        match self {
+            Self::Utf8 => byte.is_ascii().then_some(byte as char),
+            Self::Latin1 => Some(byte as char),
+            Self::Windows1252 => match byte {
+                0x80..=0x9F => WINDOWS_1252_C1[(byte - 0x80) as usize],
+                _ => Some(byte as char),
+            },
+            Self::Ebcdic => Some(EBCDIC_037[byte as usize]),
+        }
+    }
+
+    /// Returns the single byte that encodes a delimiter, if there is one.
+    pub fn encode_delimiter(&self, delimiter: char) -> Option<u8> {
+        match self {
+            Self::Ebcdic => EBCDIC_037
+                .iter()
+                .position(|&c| c == delimiter)
+                .map(|byte| byte as u8),
+            _ => (0..=u8::MAX).find(|&byte| self.decode_byte(byte) == Some(delimiter)),
+        }
+    }
+
+    pub fn is_whitespace(&self, byte: u8) -> bool {
+        self.decode_byte(byte).is_some_and(char::is_whitespace)
+    }
+
+    fn undecodable(&self, offset: usize) -> EdiError {
+        EdiError::UndecodableData {
+            encoding: self.name().to_string(),
+            offset,
+        }
+    }
+}
+
+/// Windows-1252 characters for bytes 0x80-0x9F; the rest match Latin-1.
+const WINDOWS_1252_C1: [Option<char>; 32] = [
+    Some('\u{20AC}'), None, Some('\u{201A}'), Some('\u{0192}'),
+    Some('\u{201E}'), Some('\u{2026}'), Some('\u{2020}'), Some('\u{2021}'),
+    Some('\u{02C6}'), Some('\u{2030}'), Some('\u{0160}'), Some('\u{2039}'),
+    Some('\u{0152}'), None, Some('\u{017D}'), None,
+    None, Some('\u{2018}'), Some('\u{2019}'), Some('\u{201C}'),
+    Some('\u{201D}'), Some('\u{2022}'), Some('\u{2013}'), Some('\u{2014}'),
+    Some('\u{02DC}'), Some('\u{2122}'), Some('\u{0161}'), Some('\u{203A}'),
+    Some('\u{0153}'), None, Some('\u{017E}'), Some('\u{0178}'),
+];
+
+/// EBCDIC code page 037 (US/Canada) to Unicode.
+const EBCDIC_037: [char; 256] = [
+    '\u{00}', '\u{01}', '\u{02}', '\u{03}', '\u{9C}', '\u{09}', '\u{86}', '\u{7F}',
+    '\u{97}', '\u{8D}', '\u{8E}', '\u{0B}', '\u{0C}', '\u{0D}', '\u{0E}', '\u{0F}',
+    '\u{10}', '\u{11}', '\u{12}', '\u{13}', '\u{9D}', '\u{85}', '\u{08}', '\u{87}',
+    '\u{18}', '\u{19}', '\u{92}', '\u{8F}', '\u{1C}', '\u{1D}', '\u{1E}', '\u{1F}',
+    '\u{80}', '\u{81}', '\u{82}', '\u{83}', '\u{84}', '\u{0A}', '\u{17}', '\u{1B}',
+    '\u{88}', '\u{89}', '\u{8A}', '\u{8B}', '\u{8C}', '\u{05}', '\u{06}', '\u{07}',
+    '\u{90}', '\u{91}', '\u{16}', '\u{93}', '\u{94}', '\u{95}', '\u{96}', '\u{04}',
+    '\u{98}', '\u{99}', '\u{9A}', '\u{9B}', '\u{14}', '\u{15}', '\u{9E}', '\u{1A}',
+    '\u{20}', '\u{A0}', '\u{E2}', '\u{E4}', '\u{E0}', '\u{E1}', '\u{E3}', '\u{E5}',
+    '\u{E7}', '\u{F1}', '\u{A2}', '\u{2E}', '\u{3C}', '\u{28}', '\u{2B}', '\u{7C}',
+    '\u{26}', '\u{E9}', '\u{EA}', '\u{EB}', '\u{E8}', '\u{ED}', '\u{EE}', '\u{EF}',
+    '\u{EC}', '\u{DF}', '\u{21}', '\u{24}', '\u{2A}', '\u{29}', '\u{3B}', '\u{AC}',
+    '\u{2D}', '\u{2F}', '\u{C2}', '\u{C4}', '\u{C0}', '\u{C1}', '\u{C3}', '\u{C5}',
+    '\u{C7}', '\u{D1}', '\u{A6}', '\u{2C}', '\u{25}', '\u{5F}', '\u{3E}', '\u{3F}',
+    '\u{F8}', '\u{C9}', '\u{CA}', '\u{CB}', '\u{C8}', '\u{CD}', '\u{CE}', '\u{CF}',
+    '\u{CC}', '\u{60}', '\u{3A}', '\u{23}', '\u{40}', '\u{27}', '\u{3D}', '\u{22}',
+    '\u{D8}', '\u{61}', '\u{62}', '\u{63}', '\u{64}', '\u{65}', '\u{66}', '\u{67}',
+    '\u{68}', '\u{69}', '\u{AB}', '\u{BB}', '\u{F0}', '\u{FD}', '\u{FE}', '\u{B1}',
+    '\u{B0}', '\u{6A}', '\u{6B}', '\u{6C}', '\u{6D}', '\u{6E}', '\u{6F}', '\u{70}',
+    '\u{71}', '\u{72}', '\u{AA}', '\u{BA}', '\u{E6}', '\u{B8}', '\u{C6}', '\u{A4}',
+    '\u{B5}', '\u{7E}', '\u{73}', '\u{74}', '\u{75}', '\u{76}', '\u{77}', '\u{78}',
+    '\u{79}', '\u{7A}', '\u{A1}', '\u{BF}', '\u{D0}', '\u{DD}', '\u{DE}', '\u{AE}',
+    '\u{5E}', '\u{A3}', '\u{A5}', '\u{B7}', '\u{A9}', '\u{A7}', '\u{B6}', '\u{BC}',
+    '\u{BD}', '\u{BE}', '\u{5B}', '\u{5D}', '\u{AF}', '\u{A8}', '\u{B4}', '\u{D7}',
+    '\u{7B}', '\u{41}', '\u{42}', '\u{43}', '\u{44}', '\u{45}', '\u{46}', '\u{47}',
+    '\u{48}', '\u{49}', '\u{AD}', '\u{F4}', '\u{F6}', '\u{F2}', '\u{F3}', '\u{F5}',
+    '\u{7D}', '\u{4A}', '\u{4B}', '\u{4C}', '\u{4D}', '\u{4E}', '\u{4F}', '\u{50}',
+    '\u{51}', '\u{52}', '\u{B9}', '\u{FB}', '\u{FC}', '\u{F9}', '\u{FA}', '\u{FF}',
+    '\u{5C}', '\u{F7}', '\u{53}', '\u{54}', '\u{55}', '\u{56}', '\u{57}', '\u{58}',
+    '\u{59}', '\u{5A}', '\u{B2}', '\u{D4}', '\u{D6}', '\u{D2}', '\u{D3}', '\u{D5}',
+    '\u{30}', '\u{31}', '\u{32}', '\u{33}', '\u{34}', '\u{35}', '\u{36}', '\u{37}',
+    '\u{38}', '\u{39}', '\u{B3}', '\u{DB}', '\u{DC}', '\u{D9}', '\u{DA}', '\u{9F}',
+];
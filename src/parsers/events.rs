@@ -0,0 +1,159 @@
+use super::{common::LineCounter, ParserOptions};
+use crate::{
+    models::{Delimiters, IsaHeader, SegmentRef, SourcePosition},
+    error::EdiError,
+};
+
+/// What a segment means for the envelope structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    StartInterchange,
+    StartGroup,
+    StartTransaction,
+    Segment,
+    EndTransaction,
+    EndGroup,
+    EndInterchange,
+}
+
+impl EventKind {
+    fn from_segment_id(id: &str) -> Self {
+        match id {
+            "ISA" => Self::StartInterchange,
+            "GS" => Self::StartGroup,
+            "ST" => Self::StartTransaction,
+            "SE" => Self::EndTransaction,
+            "GE" => Self::EndGroup,
+            "IEA" => Self::EndInterchange,
+            _ => Self::Segment,
+        }
+    }
+}
+
+/// A single parse event. The segment borrows from the input and carries the
+/// delimiters of the interchange it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct X12Event<'a> {
+    pub kind: EventKind,
+    pub segment: SegmentRef<'a>,
+    /// Where the segment starts in the input.
+    pub position: SourcePosition,
+}
+
+/// Pull parser that yields one event per segment without building a tree.
+///
+/// Events are emitted purely from segment ids; envelope structure is not
+/// checked here, so callers that only need a few fields pay for nothing else.
+pub struct X12EventReader<'a> {
+    input: &'a str,
+    options: ParserOptions,
+    counter: LineCounter,
+    segment_index: usize,
+    isa_header: Option<IsaHeader>,
+    finished: bool,
+}
+
+impl<'a> X12EventReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    /// Reads `input` applying the trimming, trailing separator and limit
+    /// settings of `options`.
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
+        Self {
+            input,
+            options,
+            counter: LineCounter::default(),
+            segment_index: 0,
+            isa_header: None,
+            finished: false,
+        }
+    }
+
+    /// The ISA header of the interchange currently being read, or of the
+    /// last one read once its IEA has passed.
+    pub fn isa_header(&self) -> Option<&IsaHeader> {
+        self.isa_header.as_ref()
+    }
+
+    /// The delimiters declared by [`isa_header`](Self::isa_header).
+    pub fn delimiters(&self) -> Option<Delimiters> {
+        self.isa_header.as_ref().map(|header| header.delimiters)
+    }
+
+    /// Byte offset of the next unread segment.
+    pub fn offset(&self) -> usize {
+        self.counter.byte_offset()
+    }
+
+    /// Moves the cursor over `text`, which must start at the cursor.
+    fn skip(&mut self, text: &str) {
+        self.counter.advance(text.chars(), text.len());
+    }
+
+    fn next_event(&mut self) -> Result<Option<X12Event<'a>>, EdiError> {
+        loop {
+            let input = self.input;
+            let rest = &input[self.offset()..];
+            let trimmed = rest.trim_start();
+            if trimmed.is_empty() {
+                self.skip(rest);
+                return Ok(None);
+            }
+            self.skip(&rest[..rest.len() - trimmed.len()]);
+            let position = self.counter.position(self.segment_index);
+
+            // Every ISA declares the delimiters for the segments up to its
+            // IEA, so they are re-read whenever a new interchange starts.
+            // Anything else after an IEA keeps the previous delimiters and
+            // is left for the envelope checks to report.
+            let delimiters = match self.delimiters() {
+                Some(delimiters) if !trimmed.starts_with("ISA") => delimiters,
+                _ => {
+                    let header = IsaHeader::parse(trimmed).map_err(|e| {
+                        let element = match &e {
+                            EdiError::InvalidIsaField { field, .. } => field[3..].parse().ok(),
+                            _ => None,
+                        };
+                        e.at(Some(position), element)
+                    })?;
+                    let delimiters = header.delimiters;
+                    self.isa_header = Some(header);
+                    delimiters
+                }
+            };
+
+            let (raw, consumed) = match trimmed.find(delimiters.segment) {
+                Some(end) => (&trimmed[..end], &trimmed[..end + delimiters.segment.len_utf8()]),
+                None => (trimmed, trimmed),
+            };
+            self.skip(consumed);
+            if raw.trim().is_empty() {
+                continue;
+            }
+
+            let segment = self.options.segment(raw, delimiters, position)?;
+            let kind = EventKind::from_segment_id(segment.id);
+            self.segment_index += 1;
+            return Ok(Some(X12Event { kind, segment, position }));
+        }
+    }
+}
+
+impl<'a> Iterator for X12EventReader<'a> {
+    type Item = Result<X12Event<'a>, EdiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.next_event().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            // Stop after the end of input or the first error
+            self.finished = true;
+        }
+        result
+    }
+}
@@ -1,12 +1,30 @@
 pub mod x12;
 pub mod common;
+pub mod stream;
+pub mod events;
+pub mod encoding;
+pub mod bytes;
+pub mod options;
+mod builder;
 
-use crate::{models::InterchangeControl, error::EdiError};
+use crate::{models::InterchangeControl, error::EdiError, validation::ValidationReport};
 
 pub trait EdiParser {
     fn parse(&self, input: &str) -> Result<InterchangeControl, EdiError>;
-    fn validate(&self, interchange: &InterchangeControl) -> Result<(), EdiError>;
+
+    /// Validates the whole interchange, collecting every issue found.
+    fn validate_report(&self, interchange: &InterchangeControl) -> ValidationReport;
+
+    /// Validates the interchange, failing on the first error.
+    fn validate(&self, interchange: &InterchangeControl) -> Result<(), EdiError> {
+        self.validate_report(interchange).into_result()
+    }
 }
 
 // Re-export the X12 parser and the trait
-pub use x12::X12Parser;
\ No newline at end of file
+pub use x12::X12Parser;
+pub use stream::{X12StreamReader, StreamedTransaction};
+pub use events::{X12EventReader, X12Event, EventKind};
+pub use encoding::Encoding;
+pub use bytes::{ByteSegment, X12ByteReader};
+pub use options::{ParseMode, ParserOptions};
\ No newline at end of file
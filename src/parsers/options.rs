@@ -0,0 +1,134 @@
+use crate::{
+    error::EdiError,
+    models::{Delimiters, SegmentRef, SourcePosition},
+};
+
+/// How the parser reacts to envelope structure problems such as missing
+/// trailers or segments outside any transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Fail on the first problem.
+    #[default]
+    Strict,
+    /// Recover and record each problem on the interchange, where
+    /// `validate_report` picks it up.
+    Lenient,
+}
+
+/// Parsing behaviour that differs between trading partners.
+///
+/// Applies to [`X12Parser::parse`](crate::EdiParser::parse), the other
+/// string parsing methods and [`X12Parser::events`](crate::X12Parser::events).
+/// [`X12Parser::stream`](crate::X12Parser::stream) honours everything but
+/// the mode: it stops at the first envelope problem even when lenient.
+///
+/// ```rust
+/// use edi_parser::{ParseMode, ParserOptions, X12Parser};
+///
+/// let parser = X12Parser::default().with_options(
+///     ParserOptions::default()
+///         .with_mode(ParseMode::Lenient)
+///         .with_trim_whitespace(false)
+///         .with_max_segment_length(512),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserOptions {
+    pub mode: ParseMode,
+    /// Strip spaces and line breaks around element values.
+    pub trim_whitespace: bool,
+    /// Accept segments that end with an element separator, as in
+    /// `BIG*20230101*INV-001*~`, dropping the empty trailing elements.
+    /// When false, such segments are reported according to `mode`.
+    pub allow_trailing_separator: bool,
+    /// Longest segment accepted, in characters, excluding the terminator.
+    pub max_segment_length: Option<usize>,
+    /// Most segments accepted from a single input.
+    pub max_segment_count: Option<usize>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            mode: ParseMode::Strict,
+            trim_whitespace: true,
+            allow_trailing_separator: true,
+            max_segment_length: None,
+            max_segment_count: None,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// Fails on the first envelope problem.
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    /// Recovers from envelope problems and records them for the report.
+    pub fn lenient() -> Self {
+        Self::default().with_mode(ParseMode::Lenient)
+    }
+
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_trim_whitespace(mut self, trim: bool) -> Self {
+        self.trim_whitespace = trim;
+        self
+    }
+
+    pub fn with_trailing_separator(mut self, allow: bool) -> Self {
+        self.allow_trailing_separator = allow;
+        self
+    }
+
+    pub fn with_max_segment_length(mut self, max: usize) -> Self {
+        self.max_segment_length = Some(max);
+        self
+    }
+
+    pub fn with_max_segment_count(mut self, max: usize) -> Self {
+        self.max_segment_count = Some(max);
+        self
+    }
+
+    /// Applies the trimming, trailing separator and limit settings to the
+    /// text of one non-empty segment read at `position`.
+    pub(crate) fn segment<'a>(
+        &self,
+        raw: &'a str,
+        delimiters: Delimiters,
+        position: SourcePosition,
+    ) -> Result<SegmentRef<'a>, EdiError> {
+        if let Some(max) = self.max_segment_count {
+            if position.segment_index >= max {
+                return Err(EdiError::TooManySegments { max }.at(Some(position), None));
+            }
+        }
+
+        let mut raw = if self.trim_whitespace {
+            raw.trim()
+        } else {
+            raw.trim_matches(['\r', '\n'])
+        };
+        if let Some(max) = self.max_segment_length {
+            let actual = raw.chars().count();
+            if actual > max {
+                return Err(EdiError::SegmentTooLong { max, actual }.at(Some(position), None));
+            }
+        }
+        if self.allow_trailing_separator && !raw.starts_with("ISA") {
+            raw = raw.trim_end_matches(delimiters.element);
+        }
+
+        let segment = if self.trim_whitespace {
+            SegmentRef::new(raw, delimiters)
+        } else {
+            SegmentRef::untrimmed(raw, delimiters)
+        };
+        Ok(segment.with_position(position))
+    }
+}
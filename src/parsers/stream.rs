@@ -0,0 +1,350 @@
+use std::io::BufRead;
+
+use super::{common::LineCounter, Encoding, ParserOptions};
+use crate::{
+    models::{Delimiters, IsaHeader, Segment, SourcePosition, Transaction},
+    models::isa::ISA_LENGTH,
+    error::EdiError,
+};
+
+/// A transaction together with the envelope it was read from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamedTransaction {
+    pub isa_header: IsaHeader,
+    pub gs_segment: Segment,
+    pub transaction: Transaction,
+}
+
+/// Reads transactions one at a time from any `BufRead`.
+///
+/// Only the current ISA header, GS segment and the transaction being
+/// assembled are held in memory, so memory use is bounded by the largest
+/// single transaction rather than the size of the input.
+///
+/// Segments are decoded with the reader's [`Encoding`] and trimmed and
+/// limited as set in its [`ParserOptions`], except for the mode: any
+/// envelope problem ends the stream as in
+/// [`ParseMode::Strict`](crate::ParseMode::Strict), since recovering would
+/// mean yielding transactions their envelope never closed.
+pub struct X12StreamReader<R: BufRead> {
+    reader: R,
+    options: ParserOptions,
+    encoding: Encoding,
+    buffer: Vec<u8>,
+    /// The encoded segment terminator of the current interchange.
+    terminator: u8,
+    counter: LineCounter,
+    segment_index: usize,
+    isa_header: Option<IsaHeader>,
+    /// Where the current interchange's ISA was read from.
+    isa_position: Option<SourcePosition>,
+    gs_segment: Option<Segment>,
+    current_transaction: Option<Vec<Segment>>,
+    finished: bool,
+}
+
+impl<R: BufRead> X12StreamReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            options: ParserOptions::default(),
+            encoding: Encoding::default(),
+            buffer: Vec::new(),
+            terminator: b'~',
+            counter: LineCounter::default(),
+            segment_index: 0,
+            isa_header: None,
+            isa_position: None,
+            gs_segment: None,
+            current_transaction: None,
+            finished: false,
+        }
+    }
+
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Decodes the input as `encoding` rather than UTF-8.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// The ISA header of the interchange currently being read.
+    pub fn isa_header(&self) -> Option<&IsaHeader> {
+        self.isa_header.as_ref()
+    }
+
+    /// The GS segment of the functional group currently being read.
+    pub fn gs_segment(&self) -> Option<&Segment> {
+        self.gs_segment.as_ref()
+    }
+
+    fn io_error(error: std::io::Error) -> EdiError {
+        EdiError::Io(error.to_string())
+    }
+
+    /// Position of the next segment, which starts at the current cursor.
+    fn next_position(&self) -> SourcePosition {
+        self.counter.position(self.segment_index)
+    }
+
+    /// Skips whitespace between interchanges; returns false at end of input.
+    fn skip_whitespace(&mut self) -> Result<bool, EdiError> {
+        loop {
+            let available = self.reader.fill_buf().map_err(Self::io_error)?;
+            if available.is_empty() {
+                return Ok(false);
+            }
+            let encoding = self.encoding;
+            let skipped = available.iter().take_while(|&&b| encoding.is_whitespace(b)).count();
+            let exhausted = skipped == available.len();
+            self.counter.advance(encoding.decode_lossy(&available[..skipped]).chars(), skipped);
+            self.reader.consume(skipped);
+            if !exhausted {
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Reads the fixed-width ISA that opens an interchange.
+    fn read_isa(&mut self) -> Result<Option<IsaHeader>, EdiError> {
+        if !self.skip_whitespace()? {
+            return Ok(None);
+        }
+
+        let position = self.next_position();
+        let mut bytes = vec![0u8; ISA_LENGTH];
+        self.reader
+            .read_exact(&mut bytes)
+            .map_err(|e| Self::io_error(e).at(Some(position), None))?;
+        let isa = self
+            .encoding
+            .decode(&bytes, position.byte_offset)
+            .map_err(|e| e.at(Some(position), None))?;
+        self.counter.advance(isa.chars(), bytes.len());
+
+        let header = IsaHeader::parse(&isa).map_err(|e| e.at(Some(position), None))?;
+        let delimiters = header.delimiters;
+        // The ISA counts toward the segment limits like any other segment
+        self.options.segment(isa.strip_suffix(delimiters.segment).unwrap_or(&isa), delimiters, position)?;
+        self.segment_index += 1;
+
+        self.terminator = self.encoding.encode_delimiter(delimiters.segment).ok_or_else(|| {
+            EdiError::InvalidSegmentFormat("Streaming requires a single-byte segment terminator".to_string())
+                .at(Some(position), None)
+        })?;
+        self.isa_position = Some(position);
+        Ok(Some(header))
+    }
+
+    /// Reads the next segment of the current interchange.
+    fn read_segment(&mut self, delimiters: Delimiters) -> Result<Option<Segment>, EdiError> {
+        loop {
+            self.buffer.clear();
+            let read = self
+                .reader
+                .read_until(self.terminator, &mut self.buffer)
+                .map_err(Self::io_error)?;
+            if read == 0 {
+                return Ok(None);
+            }
+
+            let encoding = self.encoding;
+            let leading = self.buffer.iter().take_while(|&&b| encoding.is_whitespace(b)).count();
+            self.counter.advance(encoding.decode_lossy(&self.buffer[..leading]).chars(), leading);
+            let position = self.next_position();
+
+            let bytes = &self.buffer[leading..];
+            let text = encoding
+                .decode(bytes, position.byte_offset)
+                .map_err(|e| e.at(Some(position), None))?;
+            self.counter.advance(text.chars(), bytes.len());
+            let raw = text.strip_suffix(delimiters.segment).unwrap_or(&text);
+            if raw.trim().is_empty() {
+                continue;
+            }
+
+            let segment = self.options.segment(raw, delimiters, position)?;
+            if !self.options.allow_trailing_separator && segment.raw().ends_with(delimiters.element) {
+                let error = EdiError::TrailingElementSeparator(segment.raw().to_string());
+                return Err(error.at(Some(position), Some(segment.element_count())));
+            }
+            self.segment_index += 1;
+            return Ok(Some(segment.to_owned()));
+        }
+    }
+
+    fn next_transaction(&mut self) -> Result<Option<StreamedTransaction>, EdiError> {
+        loop {
+            let delimiters = match &self.isa_header {
+                Some(header) => header.delimiters,
+                None => match self.read_isa()? {
+                    Some(header) => {
+                        let delimiters = header.delimiters;
+                        self.isa_header = Some(header);
+                        delimiters
+                    }
+                    None => return Ok(None),
+                },
+            };
+
+            let segment = match self.read_segment(delimiters)? {
+                Some(segment) => segment,
+                None => {
+                    if let Some(segments) = self.current_transaction.take() {
+                        let control_number = segments[0].elements.get(1).cloned().unwrap_or_default();
+                        return Err(EdiError::MissingRequiredSegment(format!(
+                            "SE for transaction {}",
+                            control_number
+                        )).at(segments[0].position, None));
+                    }
+                    // Unclosed levels are reported at the header they should close
+                    if let Some(gs) = &self.gs_segment {
+                        return Err(missing_trailer("GE", gs.element(6).unwrap_or_default(), gs));
+                    }
+                    if let Some(header) = &self.isa_header {
+                        let error = EdiError::MissingTrailer {
+                            trailer: "IEA".to_string(),
+                            control_number: header.control_number().to_string(),
+                        };
+                        return Err(error.at(self.isa_position, None));
+                    }
+                    return Ok(None);
+                }
+            };
+
+            match segment.id.as_str() {
+                // An ISA is only expected after the IEA, and may use other
+                // delimiters than the interchange still open
+                id if id.starts_with("ISA") => {
+                    self.check_group_closed(&segment)?;
+                    let control_number = self.isa_header.as_ref().map(IsaHeader::control_number);
+                    let context = format!("before IEA of interchange {}", control_number.unwrap_or_default());
+                    return Err(unexpected("ISA", &segment, context));
+                }
+                "GS" => {
+                    self.check_transaction_closed(&segment)?;
+                    if let Some(gs) = &self.gs_segment {
+                        let context = format!("before GE of group {}", gs.element(6).unwrap_or_default());
+                        return Err(unexpected("GS", &segment, context));
+                    }
+                    self.gs_segment = Some(segment);
+                }
+                "GE" => {
+                    if self.gs_segment.is_none() {
+                        return Err(unexpected("GE", &segment, "with no open functional group".to_string()));
+                    }
+                    self.check_transaction_closed(&segment)?;
+                    self.gs_segment = None;
+                }
+                "IEA" => {
+                    self.check_group_closed(&segment)?;
+                    // The next interchange may declare different delimiters
+                    self.isa_header = None;
+                }
+                "ST" => {
+                    if let Some(segments) = &self.current_transaction {
+                        let control_number = segments[0].element(2).unwrap_or_default();
+                        let context = format!("before SE of transaction {}", control_number);
+                        return Err(unexpected("ST", &segment, context));
+                    }
+                    if self.gs_segment.is_none() {
+                        return Err(unexpected("ST", &segment, "outside any functional group".to_string()));
+                    }
+                    self.current_transaction = Some(vec![segment]);
+                }
+                "SE" => match self.current_transaction.take() {
+                    Some(mut segments) => {
+                        segments.push(segment);
+                        return self.finish_transaction(segments).map(Some);
+                    }
+                    None => return Err(unexpected("SE", &segment, "with no open transaction".to_string())),
+                },
+                _ => match self.current_transaction.as_mut() {
+                    Some(segments) => segments.push(segment),
+                    None => {
+                        let id = segment.id.clone();
+                        return Err(unexpected(&id, &segment, "outside any transaction".to_string()));
+                    }
+                },
+            }
+        }
+    }
+
+    /// Fails when a transaction is still open as `found` arrives.
+    fn check_transaction_closed(&self, found: &Segment) -> Result<(), EdiError> {
+        match &self.current_transaction {
+            Some(segments) => Err(missing_trailer("SE", segments[0].element(2).unwrap_or_default(), found)),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails when a transaction or group is still open as `found` arrives.
+    fn check_group_closed(&self, found: &Segment) -> Result<(), EdiError> {
+        self.check_transaction_closed(found)?;
+        match &self.gs_segment {
+            Some(gs) => Err(missing_trailer("GE", gs.element(6).unwrap_or_default(), found)),
+            None => Ok(()),
+        }
+    }
+
+    fn finish_transaction(&self, segments: Vec<Segment>) -> Result<StreamedTransaction, EdiError> {
+        let st = &segments[0];
+        let transaction_set_id = st.elements.first().cloned().ok_or_else(|| {
+            EdiError::InvalidSegmentFormat("ST segment is missing ST01".to_string())
+                .at(st.position, Some(1))
+        })?;
+        let control_number = st.elements.get(1).cloned().ok_or_else(|| {
+            EdiError::InvalidSegmentFormat("ST segment is missing ST02".to_string())
+                .at(st.position, Some(2))
+        })?;
+        let gs_segment = self
+            .gs_segment
+            .clone()
+            .ok_or_else(|| EdiError::MissingRequiredSegment(format!(
+                "GS for transaction {}",
+                control_number
+            )).at(st.position, None))?;
+        let isa_header = self
+            .isa_header
+            .clone()
+            .ok_or_else(|| EdiError::MissingRequiredSegment("ISA".to_string()).at(st.position, None))?;
+
+        Ok(StreamedTransaction {
+            isa_header,
+            gs_segment,
+            transaction: Transaction::new(segments, transaction_set_id, control_number),
+        })
+    }
+}
+
+/// `segment`, with ID `id`, is not allowed where it appears.
+fn unexpected(id: &str, segment: &Segment, context: String) -> EdiError {
+    EdiError::UnexpectedSegment { id: id.to_string(), context }.at(segment.position, None)
+}
+
+/// The trailer closing `control_number` is missing, as found at `found`.
+fn missing_trailer(trailer: &str, control_number: &str, found: &Segment) -> EdiError {
+    EdiError::MissingTrailer { trailer: trailer.to_string(), control_number: control_number.to_string() }
+        .at(found.position, None)
+}
+
+impl<R: BufRead> Iterator for X12StreamReader<R> {
+    type Item = Result<StreamedTransaction, EdiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.next_transaction().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            // Stop after the end of input or the first error
+            self.finished = true;
+        }
+        result
+    }
+}
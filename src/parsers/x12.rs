@@ -1,23 +1,22 @@
-use super::EdiParser;
+use std::borrow::Cow;
+use std::io::BufRead;
+
+use super::{builder::TreeBuilder, EdiParser, Encoding, ParseMode, ParserOptions, X12ByteReader, X12StreamReader, X12EventReader, EventKind};
 use crate::{
-    models::{InterchangeControl, FunctionalGroup, Transaction, Segment, X12Version},
+    models::{InterchangeControl, Delimiters, InterchangeRef},
     error::EdiError,
+    validation::{
+        CodeListRegistry, PartnerProfile, SchemaRegistry, SegmentValidator, TransactionSetSchema, TransactionValidator,
+        ValidationReport, Validator,
+    },
 };
 
+#[derive(Clone, Default)]
 pub struct X12Parser {
-    element_separator: char,
-    segment_separator: char,
-    sub_element_separator: char,
-}
-
-impl Default for X12Parser {
-    fn default() -> Self {
-        Self {
-            element_separator: '*',
-            segment_separator: '~',
-            sub_element_separator: '>',
-        }
-    }
+    delimiters: Delimiters,
+    encoding: Encoding,
+    options: ParserOptions,
+    validator: Validator,
 }
 
 impl X12Parser {
@@ -27,215 +26,277 @@ impl X12Parser {
         sub_element_separator: char,
     ) -> Self {
         Self {
-            element_separator,
-            segment_separator,
-            sub_element_separator,
+            delimiters: Delimiters {
+                element: element_separator,
+                segment: segment_separator,
+                component: sub_element_separator,
+                repetition: None,
+            },
+            encoding: Encoding::default(),
+            options: ParserOptions::default(),
+            validator: Validator::default(),
         }
     }
 
-    fn trim_whitespace(&self, s: &str) -> String {
-        s.trim().to_string()
+    /// Sets the character encoding used by [`X12Parser::parse_bytes`] and
+    /// [`X12Parser::byte_segments`].
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
     }
 
-    fn parse_segment(&self, line: &str) -> Result<Segment, EdiError> {
-        let elements: Vec<String> = line
-            .split(self.element_separator)
-            .map(|s| self.trim_whitespace(s)) // Trim whitespace from each element
-            .collect();
-        
-        if elements.is_empty() {
-            return Err(EdiError::InvalidSegmentFormat(line.to_string()));
-        }
+    /// Sets how input is parsed; see [`ParserOptions`].
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
 
-        Ok(Segment::new(elements[0].clone(), elements[1..].to_vec()))
+    pub fn options(&self) -> ParserOptions {
+        self.options
     }
 
-    fn extract_delimiters_from_isa(&self, isa_segment: &str) -> Result<(char, char, char), EdiError> {
-        let elements: Vec<&str> = isa_segment.split('*').collect();
-        if elements.len() < 16 {
-            return Err(EdiError::InvalidSegmentFormat(isa_segment.to_string()));
-        }
+    /// Chooses whether envelope structure problems fail the parse or are
+    /// recorded on the interchange for [`EdiParser::validate_report`].
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
 
-        let element_separator = '*';
-        let segment_separator = elements[15].chars().next().unwrap_or('~');
-        let sub_element_separator = elements[16].chars().next().unwrap_or('>');
+    /// Replaces everything `validate` checks interchanges against.
+    pub fn with_validator(mut self, validator: Validator) -> Self {
+        self.validator = validator;
+        self
+    }
 
-        Ok((element_separator, segment_separator, sub_element_separator))
+    pub fn validator(&self) -> &Validator {
+        &self.validator
     }
 
-    fn parse_isa_segment(&self, isa_line: &str) -> Result<Segment, EdiError> {
-        // ISA segment has fixed-width fields, handle it specially
-        let trimmed = self.trim_whitespace(isa_line);
-        let elements: Vec<String> = trimmed
-            .split('*')
-            .map(|s| self.trim_whitespace(s))
-            .collect();
-        
-        if elements.len() < 16 {
-            return Err(EdiError::InvalidSegmentFormat(isa_line.to_string()));
-        }
+    /// Replaces the schemas `validate` checks transactions against.
+    pub fn with_schemas(mut self, schemas: SchemaRegistry) -> Self {
+        self.validator = self.validator.with_schemas(schemas);
+        self
+    }
 
-        Ok(Segment::new("ISA".to_string(), elements[1..].to_vec()))
+    /// Adds a schema, replacing any for the same transaction set and version.
+    pub fn with_schema(mut self, schema: TransactionSetSchema) -> Self {
+        self.validator = self.validator.with_schema(schema);
+        self
     }
-}
 
-impl EdiParser for X12Parser {
-    fn parse(&self, input: &str) -> Result<InterchangeControl, EdiError> {
-        let segments: Vec<&str> = input
-            .split(self.segment_separator)
-            .filter(|s| !s.trim().is_empty())
-            .collect();
+    pub fn schemas(&self) -> &SchemaRegistry {
+        self.validator.schemas()
+    }
 
-        if segments.is_empty() {
-            return Err(EdiError::InvalidSegmentFormat("Empty input".to_string()));
-        }
+    /// Replaces the code lists identifier elements are checked against.
+    pub fn with_code_lists(mut self, code_lists: CodeListRegistry) -> Self {
+        self.validator = self.validator.with_code_lists(code_lists);
+        self
+    }
+
+    pub fn code_lists(&self) -> &CodeListRegistry {
+        self.validator.code_lists()
+    }
+
+    /// Runs `validator` on every `segment_id` segment of `transaction_set`
+    /// transactions during `validate`; see [`Validator::with_segment_validator`].
+    pub fn with_segment_validator(
+        mut self,
+        transaction_set: &str,
+        segment_id: &str,
+        validator: impl SegmentValidator + 'static,
+    ) -> Self {
+        self.validator = self.validator.with_segment_validator(transaction_set, segment_id, validator);
+        self
+    }
 
-        // Parse ISA segment first to get actual delimiters
-        let isa_segment = self.parse_isa_segment(segments[0])?;
-        let (actual_element_sep, actual_segment_sep, actual_sub_element_sep) = 
-            self.extract_delimiters_from_isa(segments[0])?; // Fixed: use self. instead of Self::
+    /// Runs `validator` on every `transaction_set` transaction during
+    /// `validate`.
+    pub fn with_transaction_validator(
+        mut self,
+        transaction_set: &str,
+        validator: impl TransactionValidator + 'static,
+    ) -> Self {
+        self.validator = self.validator.with_transaction_validator(transaction_set, validator);
+        self
+    }
+
+    /// Adds a trading partner profile, applied by `validate` to the groups
+    /// the partner sends; see [`PartnerProfile`].
+    pub fn with_profile(mut self, profile: PartnerProfile) -> Self {
+        self.validator = self.validator.with_profile(profile);
+        self
+    }
 
-        let parser = if actual_element_sep != self.element_separator || 
-            actual_segment_sep != self.segment_separator {
-            X12Parser::with_delimiters(actual_element_sep, actual_segment_sep, actual_sub_element_sep)
-        } else {
-            self.clone()
+    /// Delimiters used when writing outbound interchanges. Inbound
+    /// interchanges are always read with the delimiters declared in their ISA.
+    pub fn delimiters(&self) -> Delimiters {
+        self.delimiters
+    }
+
+    /// Reads transactions incrementally from `reader` instead of loading the
+    /// whole input into memory, with the parser's options and encoding.
+    /// Envelope problems end the stream even in lenient mode; see
+    /// [`X12StreamReader`].
+    pub fn stream<R: BufRead>(&self, reader: R) -> X12StreamReader<R> {
+        X12StreamReader::new(reader).with_options(self.options).with_encoding(self.encoding)
+    }
+
+    /// Splits raw bytes into segments whose elements are decoded on demand.
+    pub fn byte_segments<'a>(&self, input: &'a [u8]) -> X12ByteReader<'a> {
+        X12ByteReader::new(input, self.encoding)
+    }
+
+    /// Parses raw bytes in the configured encoding.
+    ///
+    /// Undecodable data is reported as [`EdiError::UndecodableData`] with the
+    /// byte offset in `input`.
+    pub fn parse_bytes(&self, input: &[u8]) -> Result<InterchangeControl, EdiError> {
+        let mut interchanges = self.decode_and_parse(input, |text| self.parse(text).map(|i| vec![i]))?;
+        Ok(interchanges.remove(0))
+    }
+
+    /// Parses every interchange in raw bytes in the configured encoding.
+    pub fn parse_all_bytes(&self, input: &[u8]) -> Result<Vec<InterchangeControl>, EdiError> {
+        self.decode_and_parse(input, |text| self.parse_all(text))
+    }
+
+    /// Decodes `input` and parses it, keeping byte offsets relative to `input`.
+    fn decode_and_parse(
+        &self,
+        input: &[u8],
+        parse: impl Fn(&str) -> Result<Vec<InterchangeControl>, EdiError>,
+    ) -> Result<Vec<InterchangeControl>, EdiError> {
+        let text = self.encoding.decode(input, 0)?;
+        let Cow::Owned(text) = text else {
+            // The text is the input itself, so offsets already match
+            return parse(&text);
         };
 
-        let mut functional_groups = Vec::new();
-        let mut current_fg: Option<FunctionalGroup> = None;
-        let mut current_transaction: Option<Transaction> = None;
-        let mut _transaction_count = 0;
-
-        for segment_str in segments.iter().skip(1) {
-            let segment = parser.parse_segment(segment_str)?;
-            
-            match segment.id.as_str() {
-                "GS" => {
-                    if let Some(fg) = current_fg.take() {
-                        functional_groups.push(fg);
-                    }
-                    current_fg = Some(FunctionalGroup {
-                        gs_segment: segment,
-                        ge_segment: None,
-                        transactions: Vec::new(),
-                    });
-                }
-                "GE" => {
-                    if let Some(mut fg) = current_fg.take() {
-                        fg.ge_segment = Some(segment);
-                        functional_groups.push(fg);
-                    }
-                }
-                "ST" => {
-                    if let Some(transaction) = current_transaction.take() {
-                        if let Some(fg) = current_fg.as_mut() {
-                            fg.transactions.push(transaction);
+        // Single-byte encodings decode one character per byte, so the byte
+        // offset in the input is the character offset in the decoded text
+        let mut offsets = CharOffsets::new(&text);
+        match parse(&text) {
+            Ok(mut interchanges) => {
+                for interchange in &mut interchanges {
+                    for segment in interchange.segments_mut() {
+                        if let Some(position) = segment.position.as_mut() {
+                            position.byte_offset = offsets.map(position.byte_offset);
                         }
                     }
-                    let transaction_set_id = segment.elements.get(0)
-                        .ok_or_else(|| EdiError::InvalidSegmentFormat(segment_str.to_string()))?
-                        .clone();
-                    let control_number = segment.elements.get(1)
-                        .ok_or_else(|| EdiError::InvalidSegmentFormat(segment_str.to_string()))?
-                        .clone();
-                    
-                    current_transaction = Some(Transaction::new(
-                        vec![segment],
-                        transaction_set_id,
-                        control_number,
-                    ));
-                    _transaction_count += 1;
-                }
-                "SE" => {
-                    if let Some(mut transaction) = current_transaction.take() {
-                        transaction.segments.push(segment);
-                        if let Some(fg) = current_fg.as_mut() {
-                            fg.transactions.push(transaction);
+                    for issue in &mut interchange.envelope_issues {
+                        if let Some(position) = issue.location.position.as_mut() {
+                            position.byte_offset = offsets.map(position.byte_offset);
                         }
                     }
                 }
-                _ => {
-                    if let Some(transaction) = current_transaction.as_mut() {
-                        transaction.segments.push(segment);
-                    }
-                }
+                Ok(interchanges)
             }
-        }
-
-        // Handle any remaining transactions or functional groups
-        if let Some(transaction) = current_transaction.take() {
-            if let Some(fg) = current_fg.as_mut() {
-                fg.transactions.push(transaction);
+            Err(EdiError::Located { error, mut position, element }) => {
+                position.byte_offset = offsets.map(position.byte_offset);
+                let error = match *error {
+                    EdiError::ParseError(_, message) => EdiError::ParseError(position.byte_offset, message),
+                    error => error,
+                };
+                Err(error.at(Some(position), element))
             }
+            Err(error) => Err(error),
         }
+    }
 
-        if let Some(fg) = current_fg.take() {
-            functional_groups.push(fg);
-        }
+    /// Returns a pull parser yielding one event per segment of `input`.
+    pub fn events<'a>(&self, input: &'a str) -> X12EventReader<'a> {
+        X12EventReader::with_options(input, self.options)
+    }
 
-        let iea_segment = segments.iter()
-            .find(|s| s.starts_with("IEA"))
-            .map(|s| parser.parse_segment(s))
-            .transpose()?;
+    /// Parses an interchange without copying any segment data.
+    ///
+    /// Segments in the returned tree borrow from `input`; call
+    /// [`InterchangeRef::to_owned`] to convert it to an `InterchangeControl`.
+    /// Input holding more than one interchange is rejected; use
+    /// [`X12Parser::parse_all_borrowed`] for those files.
+    pub fn parse_borrowed<'a>(&self, input: &'a str) -> Result<InterchangeRef<'a>, EdiError> {
+        let mut interchanges = self.build(input)?.into_iter();
 
-        let version = X12Version::from_isa(&isa_segment)?;
+        let interchange = interchanges.next()
+            .ok_or_else(|| EdiError::InvalidSegmentFormat("Empty input".to_string()))?;
+        if let Some(second) = interchanges.next() {
+            let position = second.isa_position.unwrap_or_default();
+            return Err(EdiError::ParseError(
+                position.byte_offset,
+                "Unexpected second interchange; use parse_all for multiple interchanges".to_string(),
+            ).at(Some(position), None));
+        }
 
-        Ok(InterchangeControl {
-            isa_segment,
-            iea_segment,
-            functional_groups,
-            version,
-        })
+        Ok(interchange)
     }
 
-    fn validate(&self, interchange: &InterchangeControl) -> Result<(), EdiError> {
-        // Basic validation logic
-        if interchange.isa_segment.id != "ISA" {
-            return Err(EdiError::MissingRequiredSegment("ISA".to_string()));
-        }
+    /// Parses every ISA...IEA envelope in `input`, in order.
+    pub fn parse_all_borrowed<'a>(&self, input: &'a str) -> Result<Vec<InterchangeRef<'a>>, EdiError> {
+        self.build(input)
+    }
 
-        if let Some(iea) = &interchange.iea_segment {
-            if iea.id != "IEA" {
-                return Err(EdiError::InvalidControlStructure);
-            }
-        }
+    /// Parses every ISA...IEA envelope in `input` into owned interchanges.
+    pub fn parse_all(&self, input: &str) -> Result<Vec<InterchangeControl>, EdiError> {
+        self.parse_all_borrowed(input)
+            .map(|interchanges| interchanges.iter().map(InterchangeRef::to_owned).collect())
+    }
 
-        // Validate each transaction has required segments and validate segment content
-        for fg in &interchange.functional_groups {
-            for transaction in &fg.transactions {
-                let required_segments = transaction.transaction_type.required_segments();
-                let segment_ids: Vec<String> = transaction.segments.iter().map(|s| s.id.clone()).collect();
-                
-                for required in required_segments {
-                    if !segment_ids.contains(&required.to_string()) {
-                        return Err(EdiError::MissingRequiredSegment(
-                            format!("{} in transaction {}", required, transaction.transaction_set_id)
-                        ));
-                    }
-                }
+    /// Builds the interchange tree from parse events.
+    fn build<'a>(&self, input: &'a str) -> Result<Vec<InterchangeRef<'a>>, EdiError> {
+        if input.trim().is_empty() {
+            return Err(EdiError::InvalidSegmentFormat("Empty input".to_string()));
+        }
 
-                // Validate individual segments
-                for segment in &transaction.segments {
-                    if let Err(validation_msg) = transaction.transaction_type.validate_segment(segment) {
-                        return Err(EdiError::ValidationError(
-                            format!("Transaction {}: {}", transaction.transaction_set_id, validation_msg)
-                        ));
-                    }
+        let mut events = self.events(input);
+        let mut builder = TreeBuilder::new(self.options);
+        while let Some(event) = events.next() {
+            let event = event?;
+            match event.kind {
+                EventKind::StartInterchange => {
+                    let header = events.isa_header().cloned().ok_or_else(|| {
+                        EdiError::MissingRequiredSegment("ISA".to_string())
+                    })?;
+                    builder.start_interchange(header, event.segment)?;
                 }
+                _ => builder.push(event)?,
             }
         }
 
-        Ok(())
+        builder.finish()
     }
 }
 
-impl Clone for X12Parser {
-    fn clone(&self) -> Self {
-        Self {
-            element_separator: self.element_separator,
-            segment_separator: self.segment_separator,
-            sub_element_separator: self.sub_element_separator,
+/// Converts byte offsets in a string to character offsets, scanning forward
+/// from the previous lookup.
+struct CharOffsets<'a> {
+    text: &'a str,
+    byte: usize,
+    chars: usize,
+}
+
+impl<'a> CharOffsets<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, byte: 0, chars: 0 }
+    }
+
+    fn map(&mut self, byte_offset: usize) -> usize {
+        if byte_offset < self.byte {
+            self.byte = 0;
+            self.chars = 0;
         }
+        self.chars += self.text[self.byte..byte_offset].chars().count();
+        self.byte = byte_offset;
+        self.chars
     }
-}
\ No newline at end of file
+}
+
+impl EdiParser for X12Parser {
+    fn parse(&self, input: &str) -> Result<InterchangeControl, EdiError> {
+        self.parse_borrowed(input).map(|interchange| interchange.to_owned())
+    }
+
+    fn validate_report(&self, interchange: &InterchangeControl) -> ValidationReport {
+        self.validator.validate_report(interchange)
+    }
+}
@@ -0,0 +1,407 @@
+use std::time::SystemTime;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use super::{IssueCode, Severity, ValidationIssue, ValidationReport};
+use crate::{
+    error::EdiError,
+    models::{Delimiters, FunctionalGroup, InterchangeControl, IsaHeader, Segment, Transaction, X12Version},
+};
+
+/// AK501 and AK901: whether a transaction or group was accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcknowledgmentCode {
+    /// A: accepted.
+    Accepted,
+    /// E: accepted, but errors were noted.
+    AcceptedWithErrors,
+    /// P: partially accepted; at least one transaction was rejected.
+    PartiallyAccepted,
+    /// R: rejected.
+    Rejected,
+}
+
+impl AcknowledgmentCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accepted => "A",
+            Self::AcceptedWithErrors => "E",
+            Self::PartiallyAccepted => "P",
+            Self::Rejected => "R",
+        }
+    }
+
+    fn is_accepted(&self) -> bool {
+        matches!(self, Self::Accepted | Self::AcceptedWithErrors)
+    }
+}
+
+/// Builds the 997 Functional Acknowledgments for an inbound interchange
+/// from its validation report: one 997 per functional group, wrapped in an
+/// interchange addressed back to the sender.
+///
+/// Each transaction gets an AK2 with an AK3 for every segment in error, an
+/// AK4 for every element in error and an AK5 that accepts (`A`), accepts
+/// with noted warnings (`E`) or rejects (`R`) it. Errors with no AK304 or
+/// AK403 code, such as total mismatches, only show in the AK5. AK9 accepts or rejects the
+/// group as a whole, or partially accepts (`P`) it when only some
+/// transactions were rejected.
+///
+/// ```rust
+/// use edi_parser::{EdiParser, FunctionalAcknowledgment, X12Parser};
+///
+/// let parser = X12Parser::default();
+/// let interchange = parser.parse(&std::fs::read_to_string("tests/test_files/sample_850.edi").unwrap()).unwrap();
+/// let report = parser.validate_report(&interchange);
+///
+/// let acknowledgment = FunctionalAcknowledgment::new(&interchange, &report)
+///     .with_interchange_control_number(42)
+///     .to_interchange();
+/// assert_eq!(acknowledgment.isa_header().receiver_id(), "BUYERID");
+/// assert_eq!(acknowledgment.functional_groups[0].transactions[0].transaction_set_id, "997");
+/// ```
+#[derive(Debug, Clone)]
+pub struct FunctionalAcknowledgment<'a> {
+    interchange: &'a InterchangeControl,
+    report: &'a ValidationReport,
+    interchange_control_number: u32,
+    group_control_number: u32,
+    date_time: NaiveDateTime,
+}
+
+impl<'a> FunctionalAcknowledgment<'a> {
+    /// Acknowledges `interchange` as validated in `report`, dated now (UTC)
+    /// with control numbers starting at 1.
+    pub fn new(interchange: &'a InterchangeControl, report: &'a ValidationReport) -> Self {
+        Self {
+            interchange,
+            report,
+            interchange_control_number: 1,
+            group_control_number: 1,
+            date_time: DateTime::<Utc>::from(SystemTime::now()).naive_utc(),
+        }
+    }
+
+    /// ISA13 and IEA02 of the acknowledgment interchange.
+    pub fn with_interchange_control_number(mut self, control_number: u32) -> Self {
+        self.interchange_control_number = control_number;
+        self
+    }
+
+    /// GS06 of the first acknowledgment group; later groups count up from it.
+    pub fn with_group_control_number(mut self, control_number: u32) -> Self {
+        self.group_control_number = control_number;
+        self
+    }
+
+    /// ISA09/ISA10 and GS04/GS05.
+    pub fn with_date_time(mut self, date_time: NaiveDateTime) -> Self {
+        self.date_time = date_time;
+        self
+    }
+
+    /// The 997 for the inbound group at `group_index`, with ST02 set to
+    /// `control_number`.
+    pub fn transaction(&self, group_index: usize, control_number: &str) -> Option<Transaction> {
+        let group = self.interchange.functional_groups.get(group_index)?;
+        let delimiters = self.interchange.isa_segment.delimiters;
+        let segment = |id: &str, elements: Vec<String>| build_segment(id, elements, delimiters);
+        let issues: Vec<&ValidationIssue> =
+            self.report.issues().iter().filter(|issue| issue.location.group_index == Some(group_index)).collect();
+
+        let mut segments = vec![
+            segment("ST", vec!["997".to_string(), control_number.to_string()]),
+            segment(
+                "AK1",
+                vec![
+                    group.gs_segment.element(1).unwrap_or_default().to_string(),
+                    group.gs_segment.element(6).unwrap_or_default().to_string(),
+                ],
+            ),
+        ];
+
+        let mut accepted = 0;
+        let mut codes = Vec::new();
+        for (transaction_index, transaction) in group.transactions.iter().enumerate() {
+            // Matched by position, as control numbers may repeat in a group
+            let transaction_issues: Vec<&ValidationIssue> = issues
+                .iter()
+                .copied()
+                .filter(|issue| issue.location.transaction_index == Some(transaction_index))
+                .collect();
+            let (acknowledgment, transaction_segments) = acknowledge_transaction(transaction, &transaction_issues, delimiters);
+            segments.extend(transaction_segments);
+            if acknowledgment.is_accepted() {
+                accepted += 1;
+            }
+            codes.push(acknowledgment);
+        }
+
+        let group_errors: Vec<&str> = issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error && issue.location.transaction_index.is_none())
+            .filter_map(|issue| group_error_code(issue))
+            .collect();
+        let status = if !group_errors.is_empty() || (!codes.is_empty() && accepted == 0) {
+            AcknowledgmentCode::Rejected
+        } else if accepted < codes.len() {
+            AcknowledgmentCode::PartiallyAccepted
+        } else if codes.contains(&AcknowledgmentCode::AcceptedWithErrors) {
+            AcknowledgmentCode::AcceptedWithErrors
+        } else {
+            AcknowledgmentCode::Accepted
+        };
+
+        let received = group.transactions.len();
+        let included = group
+            .ge_segment
+            .as_ref()
+            .and_then(|ge| ge.element(1))
+            .and_then(|ge01| ge01.parse::<usize>().ok())
+            .unwrap_or(received);
+        let mut ak9 = vec![status.as_str().to_string(), included.to_string(), received.to_string(), accepted.to_string()];
+        ak9.extend(group_errors.iter().take(5).map(|code| code.to_string()));
+        segments.push(segment("AK9", ak9));
+
+        let count = segments.len() + 1;
+        segments.push(segment("SE", vec![count.to_string(), control_number.to_string()]));
+        Some(Transaction::new(segments, "997".to_string(), control_number.to_string()))
+    }
+
+    /// The acknowledgment interchange: sender and receiver swapped in the
+    /// ISA and every GS, one FA group for each distinct GS02/GS03 pair of
+    /// the inbound groups, and the inbound delimiters, versions and usage
+    /// indicator.
+    pub fn to_interchange(&self) -> InterchangeControl {
+        let delimiters = self.interchange.isa_segment.delimiters;
+        let isa = self.isa_header();
+
+        let mut functional_groups: Vec<FunctionalGroup> = Vec::new();
+        let mut senders: Vec<(&str, &str)> = Vec::new();
+        for (group_index, group) in self.interchange.functional_groups.iter().enumerate() {
+            let sender = group.gs_segment.element(2).unwrap_or_default();
+            let receiver = group.gs_segment.element(3).unwrap_or_default();
+            let index = match senders.iter().position(|pair| *pair == (sender, receiver)) {
+                Some(index) => index,
+                None => {
+                    let control_number = (self.group_control_number as usize + senders.len()).to_string();
+                    let gs = build_segment(
+                        "GS",
+                        vec![
+                            "FA".to_string(),
+                            receiver.to_string(),
+                            sender.to_string(),
+                            self.date_time.format("%Y%m%d").to_string(),
+                            self.date_time.format("%H%M").to_string(),
+                            control_number,
+                            "X".to_string(),
+                            group.gs_segment.element(8).unwrap_or_default().to_string(),
+                        ],
+                        delimiters,
+                    );
+                    senders.push((sender, receiver));
+                    functional_groups.push(FunctionalGroup { gs_segment: gs, ge_segment: None, transactions: Vec::new() });
+                    functional_groups.len() - 1
+                }
+            };
+            let acknowledgment = &mut functional_groups[index];
+            let control_number = format!("{:04}", acknowledgment.transactions.len() + 1);
+            if let Some(transaction) = self.transaction(group_index, &control_number) {
+                acknowledgment.transactions.push(transaction);
+            }
+        }
+
+        for group in &mut functional_groups {
+            let control_number = group.gs_segment.element(6).unwrap_or_default().to_string();
+            group.ge_segment =
+                Some(build_segment("GE", vec![group.transactions.len().to_string(), control_number], delimiters));
+        }
+
+        let iea = build_segment(
+            "IEA",
+            vec![functional_groups.len().to_string(), isa.control_number().to_string()],
+            delimiters,
+        );
+        let isa_segment = isa.to_segment();
+        let version = X12Version::from_isa(&isa_segment).unwrap_or_else(|_| self.interchange.version.clone());
+        InterchangeControl {
+            isa_segment,
+            iea_segment: Some(iea),
+            functional_groups,
+            version,
+            envelope_issues: Vec::new(),
+        }
+    }
+
+    /// The acknowledgment interchange as X12.
+    pub fn to_edi(&self) -> String {
+        self.to_interchange().to_edi()
+    }
+
+    /// The inbound ISA with ISA05/ISA06 and ISA07/ISA08 swapped, no
+    /// authorization or security information, no TA1 requested and a new
+    /// date, time and control number.
+    fn isa_header(&self) -> IsaHeader {
+        let inbound = self.interchange.isa_header();
+        let field = |position: usize| inbound.raw(position).unwrap_or_default().to_string();
+        let fields = [
+            "00".to_string(),
+            " ".repeat(10),
+            "00".to_string(),
+            " ".repeat(10),
+            field(7),
+            field(8),
+            field(5),
+            field(6),
+            self.date_time.format("%y%m%d").to_string(),
+            self.date_time.format("%H%M").to_string(),
+            field(11),
+            field(12),
+            format!("{:09}", self.interchange_control_number % 1_000_000_000),
+            "0".to_string(),
+            field(15),
+        ];
+        let delimiters = inbound.delimiters;
+        let isa = format!(
+            "ISA{element}{}{element}{}{}",
+            fields.join(&delimiters.element.to_string()),
+            delimiters.component,
+            delimiters.segment,
+            element = delimiters.element,
+        );
+        // The fields come from a header that already parsed, so their widths
+        // and the delimiters are valid.
+        IsaHeader::parse(&isa).expect("acknowledgment ISA is well formed")
+    }
+}
+
+/// The AK2 loop for one transaction and whether it was accepted.
+fn acknowledge_transaction(
+    transaction: &Transaction,
+    issues: &[&ValidationIssue],
+    delimiters: Delimiters,
+) -> (AcknowledgmentCode, Vec<Segment>) {
+    let segment = |id: &str, elements: Vec<String>| build_segment(id, elements, delimiters);
+    let mut segments = vec![segment(
+        "AK2",
+        vec![transaction.transaction_set_id.clone(), transaction.control_number.clone()],
+    )];
+
+    let mut transaction_codes: Vec<&str> = Vec::new();
+    let mut segment_errors = false;
+    // Issues for the same segment, one after the other, share an AK3.
+    let mut current: Option<(usize, String)> = None;
+    for issue in issues.iter().filter(|issue| issue.severity == Severity::Error) {
+        if let Some(code) = transaction_error_code(issue) {
+            if !transaction_codes.contains(&code) {
+                transaction_codes.push(code);
+            }
+            continue;
+        }
+        segment_errors = true;
+        // Errors for the transaction as a whole, such as those from custom
+        // transaction validators, and errors with no AK304 or AK403 code,
+        // such as total mismatches, have no AK3 but still reject it
+        let (Some(index), Some(id)) = (issue.location.segment_index, issue.location.segment_id.as_deref()) else {
+            continue;
+        };
+        let element = element_error(issue, transaction.segments.get(index), delimiters);
+        let Some(code) = segment_error_code(issue.code).or(element.as_ref().map(|_| "8")) else {
+            continue;
+        };
+
+        if current.as_ref() != Some(&(index, id.to_string())) || element.is_none() {
+            let ak3 = vec![id.to_string(), (index + 1).to_string(), String::new(), code.to_string()];
+            segments.push(segment("AK3", ak3));
+            current = Some((index, id.to_string()));
+        }
+        if let Some(ak4) = element {
+            segments.push(segment("AK4", ak4));
+        }
+    }
+    if segment_errors {
+        transaction_codes.push("5");
+    }
+
+    let status = if !transaction_codes.is_empty() {
+        AcknowledgmentCode::Rejected
+    } else if issues.iter().any(|issue| issue.severity == Severity::Warning) {
+        AcknowledgmentCode::AcceptedWithErrors
+    } else {
+        AcknowledgmentCode::Accepted
+    };
+    let mut ak5 = vec![status.as_str().to_string()];
+    ak5.extend(transaction_codes.iter().take(5).map(|code| code.to_string()));
+    segments.push(segment("AK5", ak5));
+    (status, segments)
+}
+
+/// AK502: transaction set errors reported on the AK5 rather than an AK3.
+fn transaction_error_code(issue: &ValidationIssue) -> Option<&'static str> {
+    match (issue.code, issue.error.root()) {
+        (IssueCode::MissingTrailer, EdiError::MissingTrailer { trailer, .. }) if trailer == "SE" => Some("2"),
+        (IssueCode::ControlNumberMismatch, _) => Some("3"),
+        (IssueCode::CountMismatch, _) => Some("4"),
+        _ => None,
+    }
+}
+
+/// AK905: functional group errors.
+fn group_error_code(issue: &ValidationIssue) -> Option<&'static str> {
+    match (issue.code, issue.error.root()) {
+        (IssueCode::MissingTrailer, EdiError::MissingTrailer { trailer, .. }) if trailer == "GE" => Some("3"),
+        (IssueCode::ControlNumberMismatch, EdiError::GroupControlNumberMismatch { .. }) => Some("4"),
+        (IssueCode::CountMismatch, EdiError::TransactionCountMismatch { .. }) => Some("5"),
+        _ => None,
+    }
+}
+
+/// AK304: segment syntax errors.
+fn segment_error_code(code: IssueCode) -> Option<&'static str> {
+    match code {
+        IssueCode::UnexpectedSegment | IssueCode::SegmentNotUsed => Some("2"),
+        IssueCode::MissingSegment => Some("3"),
+        IssueCode::LoopMaxUseExceeded => Some("4"),
+        IssueCode::MaxUseExceeded => Some("5"),
+        IssueCode::UndefinedSegment => Some("6"),
+        IssueCode::SegmentOutOfOrder => Some("7"),
+        _ => None,
+    }
+}
+
+/// AK4 for an issue with a data element: its position, AK403 error code and
+/// a copy of the bad value when there is one.
+fn element_error(issue: &ValidationIssue, segment: Option<&Segment>, delimiters: Delimiters) -> Option<Vec<String>> {
+    let code = match issue.code {
+        IssueCode::MissingElement => "1",
+        IssueCode::ConditionalElementMissing => "2",
+        IssueCode::TooManyElements => "3",
+        IssueCode::ElementTooShort => "4",
+        IssueCode::ElementTooLong => "5",
+        IssueCode::InvalidDataType => "6",
+        IssueCode::InvalidCode => "7",
+        IssueCode::InvalidDate => "8",
+        IssueCode::InvalidTime => "9",
+        IssueCode::ExclusionViolated | IssueCode::ElementNotUsed => "10",
+        _ => return None,
+    };
+    let position = match issue.error.root() {
+        EdiError::TooManyElements { max, .. } => max + 1,
+        _ => issue.location.element?,
+    };
+    let mut ak4 = vec![position.to_string(), String::new(), code.to_string()];
+    let value = segment.and_then(|segment| segment.element(position)).unwrap_or_default();
+    let separators = [delimiters.element, delimiters.segment, delimiters.component];
+    if !value.is_empty() && value.chars().count() <= 99 && !value.contains(separators) {
+        ak4.push(value.to_string());
+    }
+    Some(ak4)
+}
+
+/// A segment without trailing empty elements.
+fn build_segment(id: &str, mut elements: Vec<String>, delimiters: Delimiters) -> Segment {
+    while elements.last().is_some_and(String::is_empty) {
+        elements.pop();
+    }
+    Segment::with_delimiters(id.to_string(), elements, delimiters)
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::error::EdiError;
+
+/// The valid codes of one identifier element, optionally tied to one
+/// version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeList {
+    /// Data element dictionary number, such as 98 for N101.
+    pub element: String,
+    /// GS08 of the groups the list applies to; `None` applies to any.
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub codes: BTreeSet<String>,
+}
+
+impl CodeList {
+    pub fn contains(&self, code: &str) -> bool {
+        self.codes.contains(code)
+    }
+}
+
+/// Code list files hold a `lists` array of [`CodeList`]s.
+#[derive(Deserialize)]
+struct CodeListFile {
+    lists: Vec<CodeList>,
+}
+
+lazy_static! {
+    static ref STANDARD_CODE_LISTS: Vec<CodeList> =
+        serde_json::from_str::<CodeListFile>(include_str!("schemas/codes.json"))
+            .expect("built-in code lists are valid")
+            .lists;
+}
+
+/// Valid codes for identifier elements, looked up by data element number
+/// and GS08 version.
+///
+/// The default registry holds standard lists for common elements. Lists
+/// can be replaced, extended with codes a trading partner uses or
+/// restricted to the codes a partner accepts:
+///
+/// ```rust
+/// use edi_parser::CodeListRegistry;
+///
+/// let mut codes = CodeListRegistry::default();
+/// codes.extend("98", ["ZA"]);
+/// codes.restrict("353", ["00", "05"]);
+///
+/// assert_eq!(codes.contains("98", Some("004010"), "ZA"), Some(true));
+/// assert_eq!(codes.contains("353", None, "01"), Some(false));
+/// assert_eq!(codes.contains("324", None, "PO-1"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeListRegistry {
+    lists: Vec<CodeList>,
+}
+
+impl Default for CodeListRegistry {
+    fn default() -> Self {
+        Self { lists: STANDARD_CODE_LISTS.clone() }
+    }
+}
+
+impl CodeListRegistry {
+    /// A registry without any lists, so no element is checked against one.
+    pub fn empty() -> Self {
+        Self { lists: Vec::new() }
+    }
+
+    /// Loads lists from JSON of the form
+    /// `{"lists": [{"element": "98", "codes": ["ST", "BT"]}]}`, replacing
+    /// any for the same element and version.
+    pub fn load_json(&mut self, input: &str) -> Result<(), EdiError> {
+        let file: CodeListFile =
+            serde_json::from_str(input).map_err(|e| EdiError::InvalidSchema(e.to_string()))?;
+        file.lists.into_iter().for_each(|list| self.register(list));
+        Ok(())
+    }
+
+    /// Loads lists from TOML with one `[[lists]]` table per list.
+    pub fn load_toml(&mut self, input: &str) -> Result<(), EdiError> {
+        let file: CodeListFile =
+            toml::from_str(input).map_err(|e| EdiError::InvalidSchema(e.to_string()))?;
+        file.lists.into_iter().for_each(|list| self.register(list));
+        Ok(())
+    }
+
+    /// Adds `list`, replacing any for the same element and version.
+    pub fn register(&mut self, list: CodeList) {
+        self.lists
+            .retain(|existing| existing.element != list.element || existing.version != list.version);
+        self.lists.push(list);
+    }
+
+    /// Adds `codes` to every list for `element`. Elements without a list
+    /// already accept any code and are left alone.
+    pub fn extend<I, S>(&mut self, element: &str, codes: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let codes: Vec<String> = codes.into_iter().map(Into::into).collect();
+        for list in self.lists.iter_mut().filter(|list| list.element == element) {
+            list.codes.extend(codes.iter().cloned());
+        }
+    }
+
+    /// Limits every list for `element` to `codes`, creating a list for any
+    /// version when there is none.
+    pub fn restrict<I, S>(&mut self, element: &str, codes: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let codes: BTreeSet<String> = codes.into_iter().map(Into::into).collect();
+        let mut restricted = false;
+        for list in self.lists.iter_mut().filter(|list| list.element == element) {
+            list.codes.retain(|code| codes.contains(code));
+            restricted = true;
+        }
+        if !restricted {
+            self.lists.push(CodeList { element: element.to_string(), version: None, name: None, codes });
+        }
+    }
+
+    /// The list for `element` in `version`, falling back to one for any
+    /// version.
+    pub fn get(&self, element: &str, version: Option<&str>) -> Option<&CodeList> {
+        let matching = |wanted: Option<&str>| {
+            self.lists
+                .iter()
+                .find(|list| list.element == element && list.version.as_deref() == wanted)
+        };
+        version.and_then(|version| matching(Some(version))).or_else(|| matching(None))
+    }
+
+    /// Whether `code` is valid for `element`; `None` when the element has
+    /// no list.
+    pub fn contains(&self, element: &str, version: Option<&str>, code: &str) -> Option<bool> {
+        self.get(element, version).map(|list| list.contains(code))
+    }
+
+    pub fn lists(&self) -> &[CodeList] {
+        &self.lists
+    }
+}
@@ -0,0 +1,131 @@
+use super::{IssueCode, IssueLocation, ValidationReport};
+use crate::{
+    error::EdiError,
+    models::{InterchangeControl, Segment},
+};
+
+/// Checks that every trailer echoes the control number of its header:
+/// ISA13 in IEA02, GS06 in GE02 and ST02 in SE02.
+///
+/// Missing trailers are not reported here.
+pub fn validate_control_numbers(interchange: &InterchangeControl, report: &mut ValidationReport) {
+    if let Some(iea) = &interchange.iea_segment {
+        let isa_header = interchange.isa_header();
+        let isa13 = isa_header.control_number();
+        let iea02 = iea.element(2).unwrap_or_default();
+        if isa13 != iea02 {
+            report.error(
+                IssueCode::ControlNumberMismatch,
+                trailer_location(IssueLocation::default(), iea),
+                EdiError::InterchangeControlNumberMismatch {
+                    isa13: isa13.to_string(),
+                    iea02: iea02.to_string(),
+                },
+            );
+        }
+    }
+
+    for (group_index, fg) in interchange.functional_groups.iter().enumerate() {
+        let group = IssueLocation::group(group_index, fg);
+
+        if let Some(ge) = &fg.ge_segment {
+            let gs06 = fg.gs_segment.element(6).unwrap_or_default();
+            let ge02 = ge.element(2).unwrap_or_default();
+            if gs06 != ge02 {
+                report.error(
+                    IssueCode::ControlNumberMismatch,
+                    trailer_location(group.clone(), ge),
+                    EdiError::GroupControlNumberMismatch {
+                        gs06: gs06.to_string(),
+                        ge02: ge02.to_string(),
+                    },
+                );
+            }
+        }
+
+        for (transaction_index, transaction) in fg.transactions.iter().enumerate() {
+            let Some(se) = transaction.segments.last().filter(|segment| segment.id == "SE") else {
+                continue;
+            };
+            let se_index = transaction.segments.len() - 1;
+
+            let se02 = se.element(2).unwrap_or_default();
+            if transaction.control_number != se02 {
+                report.error(
+                    IssueCode::ControlNumberMismatch,
+                    group.clone().transaction(transaction_index, transaction).segment(se_index, se).element(2),
+                    EdiError::TransactionControlNumberMismatch {
+                        st02: transaction.control_number.clone(),
+                        se02: se02.to_string(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Checks the counts carried by trailers against what they enclose: SE01
+/// against the segments from ST to SE inclusive, GE01 against the
+/// transactions in the group and IEA01 against the groups in the interchange.
+///
+/// Missing trailers are not reported here.
+pub fn validate_counts(interchange: &InterchangeControl, report: &mut ValidationReport) {
+    if let Some(iea) = &interchange.iea_segment {
+        let iea01 = iea.element(1).unwrap_or_default();
+        let actual = interchange.functional_groups.len();
+        if !count_matches(iea01, actual) {
+            report.error(
+                IssueCode::CountMismatch,
+                IssueLocation { element: Some(1), ..trailer_location(IssueLocation::default(), iea) },
+                EdiError::GroupCountMismatch { iea01: iea01.to_string(), actual },
+            );
+        }
+    }
+
+    for (group_index, fg) in interchange.functional_groups.iter().enumerate() {
+        let group = IssueLocation::group(group_index, fg);
+
+        if let Some(ge) = &fg.ge_segment {
+            let ge01 = ge.element(1).unwrap_or_default();
+            let actual = fg.transactions.len();
+            if !count_matches(ge01, actual) {
+                report.error(
+                    IssueCode::CountMismatch,
+                    IssueLocation { element: Some(1), ..trailer_location(group.clone(), ge) },
+                    EdiError::TransactionCountMismatch { ge01: ge01.to_string(), actual },
+                );
+            }
+        }
+
+        for (transaction_index, transaction) in fg.transactions.iter().enumerate() {
+            let Some(se) = transaction.segments.last().filter(|segment| segment.id == "SE") else {
+                continue;
+            };
+            let se01 = se.element(1).unwrap_or_default();
+            let actual = transaction.segments.len();
+            if !count_matches(se01, actual) {
+                report.error(
+                    IssueCode::CountMismatch,
+                    group.clone().transaction(transaction_index, transaction).segment(actual - 1, se).element(1),
+                    EdiError::SegmentCountMismatch { se01: se01.to_string(), actual },
+                );
+            }
+        }
+    }
+}
+
+/// Counts are numeric, so leading zeros are allowed.
+fn count_matches(value: &str, actual: usize) -> bool {
+    value.parse::<usize>() == Ok(actual)
+}
+
+/// Points at element 02 of a GE or IEA trailer, which sit outside any
+/// transaction.
+fn trailer_location(location: IssueLocation, trailer: &Segment) -> IssueLocation {
+    IssueLocation {
+        segment_id: Some(trailer.id.clone()),
+        position: trailer.position.or(location.position),
+        element: Some(2),
+        ..location
+    }
+}
@@ -0,0 +1,25 @@
+pub mod report;
+pub mod acknowledgment;
+pub mod envelope;
+pub mod codes;
+pub mod schema;
+mod structure;
+pub mod syntax;
+pub mod transaction_set;
+pub mod profile;
+pub mod totals;
+pub mod validator;
+
+pub use report::{IssueCode, IssueLocation, Severity, ValidationIssue, ValidationReport};
+pub use envelope::{validate_control_numbers, validate_counts};
+pub use codes::{CodeList, CodeListRegistry};
+pub use schema::{
+    DataType, ElementDefinition, LoopDefinition, SchemaRegistry, SegmentDefinition, SegmentUsage,
+    StructureNode, TransactionSetSchema, Usage,
+};
+pub use syntax::{SyntaxNote, SyntaxRule};
+pub use transaction_set::{validate_segment, validate_transaction};
+pub use validator::{SegmentValidator, TransactionValidator, Validator};
+pub use profile::{PartnerProfile, PartnerSelector};
+pub use totals::{InvoiceTotals, PurchaseOrderTotals};
+pub use acknowledgment::{AcknowledgmentCode, FunctionalAcknowledgment};
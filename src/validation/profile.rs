@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use super::{
+    validator::{SegmentRule, TransactionRule},
+    SchemaRegistry, SegmentValidator, StructureNode, TransactionSetSchema, TransactionValidator, Usage, Validator,
+};
+use crate::models::{FunctionalGroup, InterchangeControl};
+
+/// Envelope IDs a partner profile is selected by. Values are compared with
+/// the padding of the ISA fields trimmed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartnerSelector {
+    /// ISA06, the interchange sender.
+    InterchangeSender(String),
+    /// ISA08, the interchange receiver.
+    InterchangeReceiver(String),
+    /// GS02, the application sender.
+    GroupSender(String),
+    /// GS03, the application receiver.
+    GroupReceiver(String),
+}
+
+impl PartnerSelector {
+    /// Whether the selector names a functional group ID rather than an
+    /// interchange ID.
+    pub fn is_group(&self) -> bool {
+        matches!(self, Self::GroupSender(_) | Self::GroupReceiver(_))
+    }
+
+    pub fn matches(&self, interchange: &InterchangeControl, group: &FunctionalGroup) -> bool {
+        let (value, id) = match self {
+            Self::InterchangeSender(id) => (interchange.isa_segment.element(6), id),
+            Self::InterchangeReceiver(id) => (interchange.isa_segment.element(8), id),
+            Self::GroupSender(id) => (group.gs_segment.element(2), id),
+            Self::GroupReceiver(id) => (group.gs_segment.element(3), id),
+        };
+        value.is_some_and(|value| value.trim() == id.trim())
+    }
+}
+
+/// A change a profile makes to the usage in a base schema.
+#[derive(Debug, Clone, PartialEq)]
+enum UsageOverride {
+    Segment { transaction_set: String, id: String, usage: Usage },
+    Loop { transaction_set: String, id: String, usage: Usage },
+    Element { transaction_set: String, segment_id: String, position: usize, usage: Usage },
+}
+
+/// A trading partner's implementation guide, layered over the base
+/// schemas and code lists: stricter usage, restricted code lists, schemas
+/// of its own and extra rules.
+///
+/// A [`Validator`] validates each functional group against the first
+/// profile whose selectors match its GS02/GS03, or failing that its
+/// interchange's ISA06/ISA08:
+///
+/// ```rust
+/// use edi_parser::{PartnerProfile, PartnerSelector, Usage, Validator};
+///
+/// let validator = Validator::default().with_profile(
+///     PartnerProfile::new("acme")
+///         .with_selector(PartnerSelector::InterchangeSender("ACME".to_string()))
+///         .with_loop_usage("850", "N1", Usage::Mandatory)
+///         .with_segment_usage("810", "SAC", Usage::NotUsed)
+///         .with_codes("353", ["00"]),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct PartnerProfile {
+    pub name: String,
+    pub selectors: Vec<PartnerSelector>,
+    schemas: Vec<TransactionSetSchema>,
+    usage: Vec<UsageOverride>,
+    codes: Vec<(String, Vec<String>)>,
+    segment_rules: Vec<SegmentRule>,
+    transaction_rules: Vec<TransactionRule>,
+}
+
+impl PartnerProfile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            selectors: Vec::new(),
+            schemas: Vec::new(),
+            usage: Vec::new(),
+            codes: Vec::new(),
+            segment_rules: Vec::new(),
+            transaction_rules: Vec::new(),
+        }
+    }
+
+    pub fn with_selector(mut self, selector: PartnerSelector) -> Self {
+        self.selectors.push(selector);
+        self
+    }
+
+    /// Uses `schema` in place of the base schema for its transaction set
+    /// and version. Usage overrides still apply to it.
+    pub fn with_schema(mut self, schema: TransactionSetSchema) -> Self {
+        self.schemas.push(schema);
+        self
+    }
+
+    /// Sets the usage of every `id` segment in `transaction_set`, such as
+    /// [`Usage::NotUsed`] for a segment the partner does not accept. A
+    /// segment that opens a loop marked not used forbids the whole loop.
+    pub fn with_segment_usage(mut self, transaction_set: &str, id: &str, usage: Usage) -> Self {
+        self.usage.push(UsageOverride::Segment {
+            transaction_set: transaction_set.to_string(),
+            id: id.to_string(),
+            usage,
+        });
+        self
+    }
+
+    /// Sets the usage of every `id` loop in `transaction_set`.
+    pub fn with_loop_usage(mut self, transaction_set: &str, id: &str, usage: Usage) -> Self {
+        self.usage.push(UsageOverride::Loop {
+            transaction_set: transaction_set.to_string(),
+            id: id.to_string(),
+            usage,
+        });
+        self
+    }
+
+    /// Sets the usage of the element at 1-based `position` of `segment_id`
+    /// wherever the segment appears in `transaction_set`. A position the
+    /// segment does not have, including 0, changes nothing.
+    pub fn with_element_usage(mut self, transaction_set: &str, segment_id: &str, position: usize, usage: Usage) -> Self {
+        self.usage.push(UsageOverride::Element {
+            transaction_set: transaction_set.to_string(),
+            segment_id: segment_id.to_string(),
+            position,
+            usage,
+        });
+        self
+    }
+
+    /// Limits `element` to `codes`; see [`CodeListRegistry::restrict`](crate::CodeListRegistry::restrict).
+    pub fn with_codes<I, S>(mut self, element: &str, codes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.codes.push((element.to_string(), codes.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Runs `validator` on every `segment_id` segment of the partner's
+    /// `transaction_set` transactions.
+    pub fn with_segment_validator(
+        mut self,
+        transaction_set: &str,
+        segment_id: &str,
+        validator: impl SegmentValidator + 'static,
+    ) -> Self {
+        self.segment_rules.push(SegmentRule {
+            transaction_set: Some(transaction_set.to_string()),
+            segment_id: segment_id.to_string(),
+            validator: Arc::new(validator),
+        });
+        self
+    }
+
+    /// Runs `validator` on every `transaction_set` transaction the partner
+    /// sends.
+    pub fn with_transaction_validator(
+        mut self,
+        transaction_set: &str,
+        validator: impl TransactionValidator + 'static,
+    ) -> Self {
+        self.transaction_rules.push(TransactionRule {
+            transaction_set: Some(transaction_set.to_string()),
+            validator: Arc::new(validator),
+        });
+        self
+    }
+
+    /// Whether the profile applies to `group` of `interchange`, judged by
+    /// group IDs only or by interchange IDs only.
+    pub(crate) fn selects(&self, interchange: &InterchangeControl, group: &FunctionalGroup, by_group: bool) -> bool {
+        self.selectors
+            .iter()
+            .any(|selector| selector.is_group() == by_group && selector.matches(interchange, group))
+    }
+
+    /// `base` with the profile laid over it.
+    pub(crate) fn overlay(&self, base: &Validator) -> Validator {
+        let mut schemas = base.schemas().clone();
+        for schema in &self.schemas {
+            schemas.register(schema.clone());
+        }
+        let mut overlaid = SchemaRegistry::empty();
+        for schema in schemas.schemas() {
+            let mut schema = schema.clone();
+            for change in &self.usage {
+                change.apply(&mut schema);
+            }
+            overlaid.register(schema);
+        }
+
+        let mut code_lists = base.code_lists().clone();
+        for (element, codes) in &self.codes {
+            code_lists.restrict(element, codes.iter().cloned());
+        }
+
+        let mut validator = base.clone().with_schemas(overlaid).with_code_lists(code_lists);
+        validator.add_rules(&self.segment_rules, &self.transaction_rules);
+        validator
+    }
+}
+
+impl UsageOverride {
+    fn apply(&self, schema: &mut TransactionSetSchema) {
+        match self {
+            Self::Segment { transaction_set, id, usage } if *transaction_set == schema.id => {
+                set_usage(&mut schema.structure, &|node| matches!(node, StructureNode::Segment(segment) if segment.id == *id), *usage);
+            }
+            Self::Loop { transaction_set, id, usage } if *transaction_set == schema.id => {
+                set_usage(&mut schema.structure, &|node| matches!(node, StructureNode::Loop(definition) if definition.id == *id), *usage);
+            }
+            Self::Element { transaction_set, segment_id, position, usage } if *transaction_set == schema.id => {
+                let element = schema
+                    .segments
+                    .get_mut(segment_id)
+                    .and_then(|definition| definition.elements.get_mut(position.checked_sub(1)?));
+                if let Some(element) = element {
+                    element.usage = *usage;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn set_usage(nodes: &mut [StructureNode], selected: &dyn Fn(&StructureNode) -> bool, usage: Usage) {
+    for node in nodes {
+        if selected(node) {
+            match node {
+                StructureNode::Loop(definition) => definition.usage = usage,
+                StructureNode::Segment(segment) => segment.usage = usage,
+            }
+        }
+        if let StructureNode::Loop(definition) = node {
+            set_usage(&mut definition.segments, selected, usage);
+        }
+    }
+}
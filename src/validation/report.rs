@@ -0,0 +1,302 @@
+use std::fmt;
+
+use crate::{
+    error::EdiError,
+    models::{FunctionalGroup, Segment, SourcePosition, Transaction},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Machine-readable classification of a validation issue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IssueCode {
+    /// The ISA or IEA segment is not what the envelope requires.
+    InvalidEnvelope,
+    /// A trailer's control number does not match its header.
+    ControlNumberMismatch,
+    /// SE01, GE01 or IEA01 does not match what the envelope contains.
+    CountMismatch,
+    /// An SE, GE or IEA is missing before the next header or end of input.
+    MissingTrailer,
+    /// A segment appears where the envelope or the transaction set
+    /// structure does not allow it, such as outside any transaction, a
+    /// trailer with nothing open to close or a segment outside its loop.
+    UnexpectedSegment,
+    /// A segment ends with an element separator the options do not allow.
+    TrailingSeparator,
+    /// A segment required by the transaction set or one of its loops is
+    /// not present. The location names the missing segment and the index
+    /// of the segment found in its place.
+    MissingSegment,
+    /// A segment failed the transaction set's content rules or a custom
+    /// validator.
+    InvalidSegment,
+    /// A mandatory element is missing or empty.
+    MissingElement,
+    /// A segment has more elements than its definition.
+    TooManyElements,
+    /// An element value is not in the element's code list.
+    InvalidCode,
+    /// An element value does not match the element's data type.
+    InvalidDataType,
+    /// An element value is shorter than the element's minimum length.
+    ElementTooShort,
+    /// An element value is longer than the element's maximum length.
+    ElementTooLong,
+    /// A DT element does not hold a valid date.
+    InvalidDate,
+    /// A TM element does not hold a valid time.
+    InvalidTime,
+    /// A P, R, C or L syntax note requires an element that is missing.
+    ConditionalElementMissing,
+    /// An E syntax note is violated by more than one element being present.
+    ExclusionViolated,
+    /// A segment appears after segments that must follow it.
+    SegmentOutOfOrder,
+    /// A segment is not part of the transaction set.
+    UndefinedSegment,
+    /// A segment occurs more often than its position allows.
+    MaxUseExceeded,
+    /// A loop repeats more often than allowed.
+    LoopMaxUseExceeded,
+    /// A segment or loop marked not used is present.
+    SegmentNotUsed,
+    /// An element marked not used holds a value.
+    ElementNotUsed,
+    /// A control total such as CTT01 or TDS01 does not match the segments
+    /// it totals.
+    TotalMismatch,
+}
+
+impl IssueCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidEnvelope => "invalid_envelope",
+            Self::ControlNumberMismatch => "control_number_mismatch",
+            Self::CountMismatch => "count_mismatch",
+            Self::MissingTrailer => "missing_trailer",
+            Self::UnexpectedSegment => "unexpected_segment",
+            Self::TrailingSeparator => "trailing_separator",
+            Self::MissingSegment => "missing_segment",
+            Self::InvalidSegment => "invalid_segment",
+            Self::MissingElement => "missing_element",
+            Self::TooManyElements => "too_many_elements",
+            Self::InvalidCode => "invalid_code",
+            Self::InvalidDataType => "invalid_data_type",
+            Self::ElementTooShort => "element_too_short",
+            Self::ElementTooLong => "element_too_long",
+            Self::InvalidDate => "invalid_date",
+            Self::InvalidTime => "invalid_time",
+            Self::ConditionalElementMissing => "conditional_element_missing",
+            Self::ExclusionViolated => "exclusion_violated",
+            Self::SegmentOutOfOrder => "segment_out_of_order",
+            Self::UndefinedSegment => "undefined_segment",
+            Self::MaxUseExceeded => "max_use_exceeded",
+            Self::LoopMaxUseExceeded => "loop_max_use_exceeded",
+            Self::SegmentNotUsed => "segment_not_used",
+            Self::ElementNotUsed => "element_not_used",
+            Self::TotalMismatch => "total_mismatch",
+        }
+    }
+}
+
+impl fmt::Display for IssueCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where in an interchange a validation issue was found.
+///
+/// Fields narrow from the functional group down to a single element; any
+/// that do not apply to an issue are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IssueLocation {
+    /// Zero-based index of the functional group in the interchange.
+    pub group_index: Option<usize>,
+    /// GS06 of the functional group.
+    pub group_control_number: Option<String>,
+    /// Zero-based index of the transaction in its functional group.
+    pub transaction_index: Option<usize>,
+    /// ST01 of the transaction.
+    pub transaction_set_id: Option<String>,
+    /// ST02 of the transaction.
+    pub transaction_control_number: Option<String>,
+    pub segment_id: Option<String>,
+    /// Zero-based index of the segment within its transaction, where ST is 0.
+    pub segment_index: Option<usize>,
+    /// 1-based element position within the segment.
+    pub element: Option<usize>,
+    /// Where the segment was read from, when the interchange was parsed.
+    pub position: Option<SourcePosition>,
+}
+
+impl IssueLocation {
+    pub fn group(index: usize, group: &FunctionalGroup) -> Self {
+        Self {
+            group_index: Some(index),
+            group_control_number: group.gs_segment.element(6).map(str::to_string),
+            position: group.gs_segment.position,
+            ..Self::default()
+        }
+    }
+
+    pub fn transaction(mut self, index: usize, transaction: &Transaction) -> Self {
+        self.transaction_index = Some(index);
+        self.transaction_set_id = Some(transaction.transaction_set_id.clone());
+        self.transaction_control_number = Some(transaction.control_number.clone());
+        self.position = transaction.segments.first().and_then(|st| st.position);
+        self
+    }
+
+    pub fn segment(mut self, index: usize, segment: &Segment) -> Self {
+        self.segment_id = Some(segment.id.clone());
+        self.segment_index = Some(index);
+        self.position = segment.position.or(self.position);
+        self
+    }
+
+    pub fn element(mut self, position: usize) -> Self {
+        self.element = Some(position);
+        self
+    }
+}
+
+impl fmt::Display for IssueLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(control_number) = &self.group_control_number {
+            parts.push(format!("group {}", control_number));
+        }
+        if let Some(control_number) = &self.transaction_control_number {
+            parts.push(format!("transaction {}", control_number));
+        }
+        match (&self.segment_id, self.element) {
+            (Some(id), Some(element)) => parts.push(format!("{}{:02}", id, element)),
+            (Some(id), None) => parts.push(id.clone()),
+            _ => {}
+        }
+        if let Some(position) = self.position {
+            parts.push(position.to_string());
+        }
+
+        if parts.is_empty() {
+            f.write_str("interchange")
+        } else {
+            f.write_str(&parts.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub code: IssueCode,
+    pub location: IssueLocation,
+    /// The error this issue represents, without location.
+    pub error: EdiError,
+}
+
+impl ValidationIssue {
+    /// The issue as an `EdiError` carrying its source location.
+    pub fn to_error(&self) -> EdiError {
+        self.error.clone().at(self.location.position, self.location.element)
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{}] {} at {}",
+            self.severity.as_str(),
+            self.code,
+            self.error,
+            self.location
+        )
+    }
+}
+
+/// Every issue found while validating an interchange, in the order found.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, issue: ValidationIssue) {
+        self.issues.push(issue);
+    }
+
+    pub fn error(&mut self, code: IssueCode, location: IssueLocation, error: EdiError) {
+        self.push(ValidationIssue { severity: Severity::Error, code, location, error });
+    }
+
+    pub fn warning(&mut self, code: IssueCode, location: IssueLocation, error: EdiError) {
+        self.push(ValidationIssue { severity: Severity::Warning, code, location, error });
+    }
+
+    /// Appends every issue of `other`.
+    pub fn merge(&mut self, other: ValidationReport) {
+        self.issues.extend(other.issues);
+    }
+
+    pub fn issues(&self) -> &[ValidationIssue] {
+        &self.issues
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// True when no issue is an error; warnings are allowed.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.issues.len()
+    }
+
+    /// Fails with the first error in the report.
+    pub fn into_result(self) -> Result<(), EdiError> {
+        match self.errors().next() {
+            Some(issue) => Err(issue.to_error()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
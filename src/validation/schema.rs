@@ -0,0 +1,383 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use super::SyntaxNote;
+use crate::{
+    error::EdiError,
+    models::{parse_date, parse_time},
+};
+
+/// Whether a segment, loop or element must be present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Usage {
+    #[serde(rename = "M", alias = "mandatory")]
+    Mandatory,
+    #[default]
+    #[serde(rename = "O", alias = "optional")]
+    Optional,
+    /// Required or not depending on other elements of the segment.
+    #[serde(rename = "X", alias = "conditional")]
+    Conditional,
+    /// Must not be present; used by partner profiles to forbid what the
+    /// standard allows.
+    #[serde(rename = "N/U", alias = "not_used")]
+    NotUsed,
+}
+
+/// X12 simple data types, written in schemas as `AN`, `ID`, `N0`–`N9`,
+/// `R`, `DT` and `TM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum DataType {
+    #[default]
+    Alphanumeric,
+    Identifier,
+    /// Numeric with the given number of implied decimal places.
+    Numeric(u8),
+    Decimal,
+    Date,
+    Time,
+}
+
+impl DataType {
+    /// Whether `value` is well formed for this type: digits with an
+    /// optional leading minus for Nn, plus at most one decimal point for R,
+    /// a calendar date in CCYYMMDD or YYMMDD for DT, a time in
+    /// HHMM[SS[d..]] for TM, and no control characters for AN and ID.
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            Self::Alphanumeric | Self::Identifier => !value.chars().any(char::is_control),
+            Self::Numeric(_) => is_digits(value.strip_prefix('-').unwrap_or(value)),
+            Self::Decimal => {
+                let unsigned = value.strip_prefix('-').unwrap_or(value);
+                let mut parts = unsigned.splitn(2, '.');
+                let whole = parts.next().unwrap_or_default();
+                let fraction = parts.next().unwrap_or_default();
+                (is_digits(whole) || is_digits(fraction))
+                    && (whole.is_empty() || is_digits(whole))
+                    && (fraction.is_empty() || is_digits(fraction))
+            }
+            Self::Date => parse_date(value).is_some(),
+            Self::Time => parse_time(value).is_some(),
+        }
+    }
+
+    /// Length of `value` as X12 counts it: the sign and decimal point of
+    /// numeric values do not count.
+    pub fn length(&self, value: &str) -> usize {
+        match self {
+            Self::Numeric(_) | Self::Decimal => value.chars().filter(char::is_ascii_digit).count(),
+            _ => value.chars().count(),
+        }
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Alphanumeric => f.write_str("AN"),
+            Self::Identifier => f.write_str("ID"),
+            Self::Numeric(decimals) => write!(f, "N{}", decimals),
+            Self::Decimal => f.write_str("R"),
+            Self::Date => f.write_str("DT"),
+            Self::Time => f.write_str("TM"),
+        }
+    }
+}
+
+impl TryFrom<String> for DataType {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "AN" => Ok(Self::Alphanumeric),
+            "ID" => Ok(Self::Identifier),
+            "R" => Ok(Self::Decimal),
+            "DT" => Ok(Self::Date),
+            "TM" => Ok(Self::Time),
+            "N" => Ok(Self::Numeric(0)),
+            other => other
+                .strip_prefix('N')
+                .and_then(|decimals| decimals.parse::<u8>().ok())
+                .filter(|decimals| *decimals <= 9)
+                .map(Self::Numeric)
+                .ok_or_else(|| format!("unknown data type {}", other)),
+        }
+    }
+}
+
+impl From<DataType> for String {
+    fn from(data_type: DataType) -> Self {
+        data_type.to_string()
+    }
+}
+
+/// One element of a segment definition. Its position in the definition's
+/// element list is its 1-based position in the segment.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ElementDefinition {
+    /// Data element dictionary number, such as 353 for BEG01.
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub usage: Usage,
+    #[serde(default, rename = "type")]
+    pub data_type: DataType,
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Accepted values, taking the place of any code list registered for
+    /// the data element. Empty defers to the registry, and elements without
+    /// a registered list accept any value of their data type.
+    #[serde(default)]
+    pub codes: Vec<String>,
+    /// Position of the element whose value names this element's date or
+    /// time format, such as 5 for DTM06. Values in a format listed in
+    /// [`DatePeriod::FORMATS`](crate::DatePeriod::FORMATS) must match it.
+    #[serde(default)]
+    pub format_element: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SegmentDefinition {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub elements: Vec<ElementDefinition>,
+    /// Relational conditions between elements, such as `P0304`.
+    #[serde(default)]
+    pub syntax: Vec<SyntaxNote>,
+}
+
+/// Where a segment may appear in a transaction set and how often.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentUsage {
+    pub id: String,
+    #[serde(default)]
+    pub usage: Usage,
+    /// Occurrences allowed at this position; `None` means unbounded.
+    #[serde(default)]
+    pub max_use: Option<usize>,
+}
+
+/// A repeating group of segments, opened by its first segment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoopDefinition {
+    #[serde(rename = "loop")]
+    pub id: String,
+    #[serde(default)]
+    pub usage: Usage,
+    /// Repetitions allowed; `None` means unbounded.
+    #[serde(default)]
+    pub max_use: Option<usize>,
+    pub segments: Vec<StructureNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StructureNode {
+    Loop(LoopDefinition),
+    Segment(SegmentUsage),
+}
+
+impl StructureNode {
+    pub fn usage(&self) -> Usage {
+        match self {
+            Self::Loop(definition) => definition.usage,
+            Self::Segment(usage) => usage.usage,
+        }
+    }
+
+    /// The segment that must be present for this node to be present: the
+    /// segment itself, or the first segment of a loop.
+    pub fn first_segment(&self) -> Option<&str> {
+        match self {
+            Self::Loop(definition) => definition.segments.first().and_then(Self::first_segment),
+            Self::Segment(usage) => Some(&usage.id),
+        }
+    }
+}
+
+/// Rules for one transaction set, optionally tied to one version.
+///
+/// `segments` defines the elements of each segment by ID; `structure` lists
+/// where those segments appear, in order. Schemas are usually loaded from
+/// JSON or TOML:
+///
+/// ```rust
+/// use edi_parser::TransactionSetSchema;
+///
+/// let schema = TransactionSetSchema::from_toml(r#"
+///     id = "856"
+///     version = "004010"
+///
+///     [[segments.BSN.elements]]
+///     usage = "M"
+///     type = "ID"
+///     codes = ["00", "14"]
+///
+///     [[structure]]
+///     id = "ST"
+///     usage = "M"
+///     max_use = 1
+///
+///     [[structure]]
+///     id = "BSN"
+///     usage = "M"
+///     max_use = 1
+/// "#).unwrap();
+///
+/// assert_eq!(schema.segment("BSN").unwrap().elements[0].codes, vec!["00", "14"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionSetSchema {
+    /// ST01 of the transactions the schema applies to.
+    pub id: String,
+    /// GS08 of the groups the schema applies to; `None` applies to any.
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub segments: BTreeMap<String, SegmentDefinition>,
+    #[serde(default)]
+    pub structure: Vec<StructureNode>,
+}
+
+lazy_static! {
+    static ref BUILTIN_SCHEMAS: Vec<TransactionSetSchema> = [
+        include_str!("schemas/810.json"),
+        include_str!("schemas/850.json"),
+    ]
+    .iter()
+    .map(|json| TransactionSetSchema::from_json(json).expect("built-in schema is valid"))
+    .collect();
+}
+
+impl TransactionSetSchema {
+    pub fn from_json(input: &str) -> Result<Self, EdiError> {
+        serde_json::from_str(input).map_err(|e| EdiError::InvalidSchema(e.to_string()))
+    }
+
+    pub fn from_toml(input: &str) -> Result<Self, EdiError> {
+        toml::from_str(input).map_err(|e| EdiError::InvalidSchema(e.to_string()))
+    }
+
+    /// The schema shipped with the crate for `id`, if any. Built-in
+    /// schemas apply to every version.
+    pub fn builtin(id: &str) -> Option<&'static TransactionSetSchema> {
+        BUILTIN_SCHEMAS.iter().find(|schema| schema.id == id)
+    }
+
+    pub fn segment(&self, id: &str) -> Option<&SegmentDefinition> {
+        self.segments.get(id)
+    }
+
+    /// IDs of the segments that must be present, in structure order.
+    pub fn mandatory_segments(&self) -> Vec<&str> {
+        self.structure
+            .iter()
+            .filter(|node| node.usage() == Usage::Mandatory)
+            .filter_map(StructureNode::first_segment)
+            .collect()
+    }
+
+    /// IDs of every other segment in the structure, including those
+    /// inside loops and those marked not used, without repeats.
+    pub fn optional_segments(&self) -> Vec<&str> {
+        let mandatory = self.mandatory_segments();
+        let mut optional = Vec::new();
+        collect_segment_ids(&self.structure, &mut optional);
+        optional.retain(|id| !mandatory.contains(id));
+        optional
+    }
+}
+
+fn collect_segment_ids<'a>(nodes: &'a [StructureNode], ids: &mut Vec<&'a str>) {
+    for node in nodes {
+        match node {
+            StructureNode::Loop(definition) => collect_segment_ids(&definition.segments, ids),
+            StructureNode::Segment(usage) => {
+                if !ids.contains(&usage.id.as_str()) {
+                    ids.push(&usage.id);
+                }
+            }
+        }
+    }
+}
+
+/// The schemas available to a parser, looked up by transaction set ID and
+/// GS08 version.
+///
+/// The default registry holds the built-in 810 and 850 schemas. A schema
+/// registered for a specific version takes precedence over one for any
+/// version.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaRegistry {
+    schemas: Vec<TransactionSetSchema>,
+}
+
+impl Default for SchemaRegistry {
+    fn default() -> Self {
+        Self { schemas: BUILTIN_SCHEMAS.clone() }
+    }
+}
+
+impl SchemaRegistry {
+    /// A registry without any schemas.
+    pub fn empty() -> Self {
+        Self { schemas: Vec::new() }
+    }
+
+    /// Adds `schema`, replacing any with the same ID and version.
+    pub fn register(&mut self, schema: TransactionSetSchema) {
+        self.schemas
+            .retain(|existing| existing.id != schema.id || existing.version != schema.version);
+        self.schemas.push(schema);
+    }
+
+    pub fn with_schema(mut self, schema: TransactionSetSchema) -> Self {
+        self.register(schema);
+        self
+    }
+
+    pub fn load_json(&mut self, input: &str) -> Result<(), EdiError> {
+        self.register(TransactionSetSchema::from_json(input)?);
+        Ok(())
+    }
+
+    pub fn load_toml(&mut self, input: &str) -> Result<(), EdiError> {
+        self.register(TransactionSetSchema::from_toml(input)?);
+        Ok(())
+    }
+
+    /// The schema for transaction set `id` in `version`, falling back to
+    /// one registered for any version.
+    pub fn get(&self, id: &str, version: Option<&str>) -> Option<&TransactionSetSchema> {
+        let matching = |wanted: Option<&str>| {
+            self.schemas
+                .iter()
+                .find(|schema| schema.id == id && schema.version.as_deref() == wanted)
+        };
+        version.and_then(|version| matching(Some(version))).or_else(|| matching(None))
+    }
+
+    pub fn schemas(&self) -> &[TransactionSetSchema] {
+        &self.schemas
+    }
+}
+
+/// An element reference such as BEG03.
+pub(crate) fn element_reference(segment_id: &str, position: usize) -> String {
+    format!("{}{:02}", segment_id, position)
+}
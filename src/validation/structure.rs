@@ -0,0 +1,197 @@
+use super::{IssueCode, IssueLocation, StructureNode, TransactionSetSchema, Usage, ValidationReport};
+use crate::{error::EdiError, models::Segment};
+
+/// One open level of the structure: the transaction set itself or a loop
+/// iteration.
+struct Level<'s> {
+    nodes: &'s [StructureNode],
+    loop_id: Option<&'s str>,
+    /// Index of the node the last segment matched.
+    position: usize,
+    /// Occurrences of each node in this level.
+    counts: Vec<usize>,
+}
+
+impl<'s> Level<'s> {
+    fn new(nodes: &'s [StructureNode], loop_id: Option<&'s str>) -> Self {
+        Self { nodes, loop_id, position: 0, counts: vec![0; nodes.len()] }
+    }
+
+    /// The first node at or after the current position that `id` can
+    /// match. The segment that opens a loop is never matched inside it; it
+    /// starts the next iteration in the enclosing level instead.
+    fn find(&self, id: &str) -> Option<usize> {
+        let start = if self.loop_id.is_some() { self.position.max(1) } else { self.position };
+        (start..self.nodes.len()).find(|index| self.nodes[*index].first_segment() == Some(id))
+    }
+
+    /// Whether `id` appears anywhere before the current position.
+    fn passed(&self, id: &str) -> bool {
+        self.nodes[..self.position].iter().any(|node| contains(node, id))
+    }
+}
+
+fn contains(node: &StructureNode, id: &str) -> bool {
+    match node {
+        StructureNode::Loop(definition) => definition.segments.iter().any(|node| contains(node, id)),
+        StructureNode::Segment(usage) => usage.id == id,
+    }
+}
+
+/// Follows a transaction's segments through the structure of its schema,
+/// reporting segments out of order, unexpected where they appear or not in
+/// the transaction set at all, max-use overruns of segments and loops,
+/// segments and loops marked not used, and mandatory segments missing at
+/// any level.
+///
+/// Missing segments are reported at the segment after the point where they
+/// should have appeared; those still missing when the transaction ends are
+/// reported by [`finish`](Self::finish).
+pub(crate) struct StructureWalker<'s> {
+    schema: &'s TransactionSetSchema,
+    levels: Vec<Level<'s>>,
+}
+
+impl<'s> StructureWalker<'s> {
+    pub(crate) fn new(schema: &'s TransactionSetSchema) -> Self {
+        let levels = if schema.structure.is_empty() {
+            Vec::new()
+        } else {
+            vec![Level::new(&schema.structure, None)]
+        };
+        Self { schema, levels }
+    }
+
+    /// Moves past `segment`, found at `location`.
+    pub(crate) fn step(&mut self, segment: &Segment, location: &IssueLocation, report: &mut ValidationReport) {
+        if self.levels.is_empty() {
+            return;
+        }
+        let id = segment.id.as_str();
+
+        let found = (0..self.levels.len())
+            .rev()
+            .find_map(|depth| self.levels[depth].find(id).map(|index| (depth, index)));
+        let Some((depth, index)) = found else {
+            self.unmatched(id, location, report);
+            return;
+        };
+
+        while self.levels.len() > depth + 1 {
+            if let Some(level) = self.levels.pop() {
+                let end = level.nodes.len();
+                self.missing(&level, end, location, report);
+            }
+        }
+        let level = &self.levels[depth];
+        self.missing(level, index, location, report);
+
+        let level = &mut self.levels[depth];
+        level.position = index;
+        level.counts[index] += 1;
+        let count = level.counts[index];
+        let node = &level.nodes[index];
+        let trigger = match node {
+            StructureNode::Loop(definition) => definition.segments.first(),
+            StructureNode::Segment(_) => None,
+        };
+        if node.usage() == Usage::NotUsed || trigger.is_some_and(|trigger| trigger.usage() == Usage::NotUsed) {
+            report.error(
+                IssueCode::SegmentNotUsed,
+                location.clone(),
+                EdiError::SegmentNotUsed { id: id.to_string(), transaction_set: self.schema.id.clone() },
+            );
+        }
+        match node {
+            StructureNode::Segment(usage) => {
+                if usage.max_use.is_some_and(|max_use| count == max_use + 1) {
+                    report.error(
+                        IssueCode::MaxUseExceeded,
+                        location.clone(),
+                        EdiError::SegmentMaxUseExceeded {
+                            id: usage.id.clone(),
+                            max_use: usage.max_use.unwrap_or_default(),
+                        },
+                    );
+                }
+            }
+            StructureNode::Loop(definition) => {
+                if definition.max_use.is_some_and(|max_use| count == max_use + 1) {
+                    report.error(
+                        IssueCode::LoopMaxUseExceeded,
+                        location.clone(),
+                        EdiError::LoopMaxUseExceeded {
+                            id: definition.id.clone(),
+                            max_use: definition.max_use.unwrap_or_default(),
+                        },
+                    );
+                }
+                let mut inner = Level::new(&definition.segments, Some(&definition.id));
+                if let Some(first) = inner.counts.first_mut() {
+                    *first = 1;
+                }
+                self.levels.push(inner);
+            }
+        }
+    }
+
+    /// Closes every open level after the last segment, reporting the
+    /// mandatory segments and loops that never occurred at `location`.
+    pub(crate) fn finish(mut self, location: &IssueLocation, report: &mut ValidationReport) {
+        while let Some(level) = self.levels.pop() {
+            let end = level.nodes.len();
+            self.missing(&level, end, location, report);
+        }
+    }
+
+    /// Reports the mandatory nodes of `level` from its current position up
+    /// to `end` that never occurred.
+    fn missing(&self, level: &Level<'s>, end: usize, location: &IssueLocation, report: &mut ValidationReport) {
+        for index in level.position..end {
+            let node = &level.nodes[index];
+            if level.counts[index] > 0 || node.usage() != Usage::Mandatory {
+                continue;
+            }
+            let Some(id) = node.first_segment() else {
+                continue;
+            };
+            let context = match level.loop_id {
+                Some(loop_id) => format!("loop {}", loop_id),
+                None => format!("transaction {}", self.schema.id),
+            };
+            report.error(
+                IssueCode::MissingSegment,
+                IssueLocation { segment_id: Some(id.to_string()), element: None, ..location.clone() },
+                EdiError::MissingRequiredSegment(format!("{} in {}", id, context)),
+            );
+        }
+    }
+
+    /// Classifies a segment that fits nowhere from the current position.
+    fn unmatched(&self, id: &str, location: &IssueLocation, report: &mut ValidationReport) {
+        let transaction_set = self.schema.id.clone();
+        if self.levels.iter().any(|level| level.passed(id)) {
+            report.error(
+                IssueCode::SegmentOutOfOrder,
+                location.clone(),
+                EdiError::SegmentOutOfOrder { id: id.to_string(), transaction_set },
+            );
+        } else if self.schema.structure.iter().any(|node| contains(node, id)) {
+            let context = match self.levels.last().and_then(|level| level.loop_id) {
+                Some(loop_id) => format!("in loop {} of transaction set {}", loop_id, transaction_set),
+                None => format!("at this position in transaction set {}", transaction_set),
+            };
+            report.error(
+                IssueCode::UnexpectedSegment,
+                location.clone(),
+                EdiError::UnexpectedSegment { id: id.to_string(), context },
+            );
+        } else {
+            report.error(
+                IssueCode::UndefinedSegment,
+                location.clone(),
+                EdiError::UndefinedSegment { id: id.to_string(), transaction_set },
+            );
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The relational conditions X12 syntax notes express between elements of
+/// one segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxRule {
+    /// P: if any of the elements is present, all are required.
+    Paired,
+    /// R: at least one of the elements is required.
+    Required,
+    /// E: not more than one of the elements may be present.
+    Exclusion,
+    /// C: if the first element is present, all the others are required.
+    Conditional,
+    /// L: if the first element is present, at least one of the others is
+    /// required.
+    ListConditional,
+}
+
+impl SyntaxRule {
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Paired => 'P',
+            Self::Required => 'R',
+            Self::Exclusion => 'E',
+            Self::Conditional => 'C',
+            Self::ListConditional => 'L',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'P' => Some(Self::Paired),
+            'R' => Some(Self::Required),
+            'E' => Some(Self::Exclusion),
+            'C' => Some(Self::Conditional),
+            'L' => Some(Self::ListConditional),
+            _ => None,
+        }
+    }
+}
+
+/// A syntax note such as `P0304`: a rule and the 1-based positions of the
+/// elements it relates, written in schemas in the standard notation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SyntaxNote {
+    pub rule: SyntaxRule,
+    pub elements: Vec<usize>,
+}
+
+impl SyntaxNote {
+    /// Checks the note against a segment, given which element positions
+    /// hold a value. Returns the position the violation is reported at: the
+    /// first missing element for P and C, the first element of the note for
+    /// R and L, and the second element present for E.
+    pub fn violation(&self, present: impl Fn(usize) -> bool) -> Option<usize> {
+        let (first, rest) = self.elements.split_first()?;
+        let missing = || self.elements.iter().copied().find(|position| !present(*position));
+        match self.rule {
+            SyntaxRule::Paired => {
+                if self.elements.iter().any(|position| present(*position)) {
+                    missing()
+                } else {
+                    None
+                }
+            }
+            SyntaxRule::Required => {
+                if self.elements.iter().any(|position| present(*position)) {
+                    None
+                } else {
+                    Some(*first)
+                }
+            }
+            SyntaxRule::Exclusion => {
+                self.elements.iter().copied().filter(|position| present(*position)).nth(1)
+            }
+            SyntaxRule::Conditional => {
+                if present(*first) {
+                    rest.iter().copied().find(|position| !present(*position))
+                } else {
+                    None
+                }
+            }
+            SyntaxRule::ListConditional => {
+                if present(*first) && !rest.iter().any(|position| present(*position)) {
+                    rest.first().copied()
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for SyntaxNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rule.as_char())?;
+        for position in &self.elements {
+            write!(f, "{:02}", position)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for SyntaxNote {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("invalid syntax note {}", value);
+        let mut chars = value.chars();
+        let rule = chars.next().and_then(SyntaxRule::from_char).ok_or_else(invalid)?;
+        let digits = chars.as_str();
+        if digits.len() < 4 || !digits.len().is_multiple_of(2) || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let elements: Vec<usize> = (0..digits.len())
+            .step_by(2)
+            .map(|start| digits[start..start + 2].parse().unwrap_or_default())
+            .collect();
+        if elements.contains(&0) {
+            return Err(invalid());
+        }
+        Ok(Self { rule, elements })
+    }
+}
+
+impl From<SyntaxNote> for String {
+    fn from(note: SyntaxNote) -> Self {
+        note.to_string()
+    }
+}
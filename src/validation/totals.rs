@@ -0,0 +1,181 @@
+use std::fmt;
+
+use super::{IssueCode, IssueLocation, TransactionValidator, ValidationReport};
+use crate::{error::EdiError, models::Transaction};
+
+/// An exact decimal read from an R or N element: `units` scaled down by
+/// `scale` decimal places. Values are limited to 18 digits, and arithmetic
+/// that would still overflow `i128` yields `None`.
+#[derive(Debug, Clone, Copy)]
+struct Decimal {
+    units: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    const ZERO: Self = Self { units: 0, scale: 0 };
+
+    fn parse(value: &str) -> Option<Self> {
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, value),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole.is_empty() && fraction.is_empty()
+            || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+            || whole.len() + fraction.len() > 18
+        {
+            return None;
+        }
+        let units: i128 = format!("{}{}", whole, fraction).parse().ok()?;
+        Some(Self { units: if negative { -units } else { units }, scale: fraction.len() as u32 })
+    }
+
+    fn rescale(self, scale: u32) -> Option<Self> {
+        let factor = 10i128.checked_pow(scale - self.scale)?;
+        Some(Self { units: self.units.checked_mul(factor)?, scale })
+    }
+
+    fn add(self, other: Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let units = self.rescale(scale)?.units.checked_add(other.rescale(scale)?.units)?;
+        Some(Self { units, scale })
+    }
+
+    fn mul(self, other: Self) -> Option<Self> {
+        Some(Self { units: self.units.checked_mul(other.units)?, scale: self.scale + other.scale })
+    }
+
+    /// The value in hundredths, rounded half away from zero.
+    fn cents(self) -> Option<i128> {
+        if self.scale <= 2 {
+            return self.rescale(2).map(|cents| cents.units);
+        }
+        let divisor = 10i128.checked_pow(self.scale - 2)?;
+        let rounded = (self.units.abs() + divisor / 2) / divisor;
+        Some(rounded * self.units.signum())
+    }
+
+    /// Whether both hold the same value; `None` when comparing them would
+    /// overflow.
+    fn same(self, other: Self) -> Option<bool> {
+        let scale = self.scale.max(other.scale);
+        Some(self.rescale(scale)?.units == other.rescale(scale)?.units)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let divisor = 10i128.pow(self.scale);
+        let sign = if self.units < 0 { "-" } else { "" };
+        let (whole, fraction) = (self.units.abs() / divisor, self.units.abs() % divisor);
+        if self.scale == 0 {
+            write!(f, "{}{}", sign, whole)
+        } else {
+            write!(f, "{}{}.{:0width$}", sign, whole, fraction, width = self.scale as usize)
+        }
+    }
+}
+
+/// Sums one element over every segment with `segment_id`. `None` when any
+/// value is missing or not a number, which the element checks report, or
+/// when the sum overflows.
+fn sum(transaction: &Transaction, segment_id: &str, position: usize) -> Option<Decimal> {
+    transaction
+        .segments
+        .iter()
+        .filter(|segment| segment.id == segment_id)
+        .try_fold(Decimal::ZERO, |total, segment| total.add(Decimal::parse(segment.element(position)?)?))
+}
+
+/// Reports the element at `position` of the first `segment_id` segment
+/// when its value differs from `expected`. Values that are missing or not
+/// numbers, including decimals in `numeric` (Nn) elements, are left to the
+/// element checks, as are totals too large to compare.
+fn check_total(
+    transaction: &Transaction,
+    location: &IssueLocation,
+    report: &mut ValidationReport,
+    (segment_id, position, numeric): (&str, usize, bool),
+    expected: Decimal,
+    total: &str,
+) {
+    let Some((index, segment)) = transaction.segments.iter().enumerate().find(|(_, segment)| segment.id == segment_id)
+    else {
+        return;
+    };
+    let actual = segment.element(position).and_then(Decimal::parse);
+    let Some(actual) = actual.filter(|actual| !numeric || actual.scale == 0) else {
+        return;
+    };
+    if actual.same(expected) == Some(false) {
+        report.error(
+            IssueCode::TotalMismatch,
+            location.clone().segment(index, segment).element(position),
+            EdiError::TotalMismatch {
+                reference: format!("{}{:02}", segment_id, position),
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+                total: total.to_string(),
+            },
+        );
+    }
+}
+
+/// Checks the CTT totals of an 850: CTT01 against the number of PO1 line
+/// items and CTT02 against the hash total of their PO102 quantities.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PurchaseOrderTotals;
+
+impl TransactionValidator for PurchaseOrderTotals {
+    fn validate(&self, transaction: &Transaction, location: &IssueLocation, report: &mut ValidationReport) {
+        let line_items = transaction.segments.iter().filter(|segment| segment.id == "PO1").count();
+        let line_items = Decimal { units: line_items as i128, scale: 0 };
+        check_total(transaction, location, report, ("CTT", 1, true), line_items, "number of PO1 line items");
+        if let Some(quantities) = sum(transaction, "PO1", 2) {
+            check_total(transaction, location, report, ("CTT", 2, false), quantities, "hash total of PO102 quantities");
+        }
+    }
+}
+
+/// Checks TDS01 of an 810 against the invoice lines: the sum of IT102
+/// quantity times IT104 unit price, each rounded to the cent, plus SAC05 of
+/// every charge (SAC01 `C`) less every allowance (SAC01 `A`). Both sides are
+/// in implied cents, so TDS*25000 is 250.00.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InvoiceTotals;
+
+impl TransactionValidator for InvoiceTotals {
+    fn validate(&self, transaction: &Transaction, location: &IssueLocation, report: &mut ValidationReport) {
+        let mut cents: i128 = 0;
+        for segment in &transaction.segments {
+            let amount = match (segment.id.as_str(), segment.element(1)) {
+                ("IT1", _) => {
+                    let quantity = segment.element(2).and_then(Decimal::parse);
+                    let price = segment.element(4).and_then(Decimal::parse);
+                    match (quantity, price) {
+                        (Some(quantity), Some(price)) => quantity.mul(price).and_then(Decimal::cents),
+                        _ => return,
+                    }
+                }
+                ("SAC", Some(indicator @ ("A" | "C"))) => {
+                    let Some(amount) = segment.element(5).filter(|amount| !amount.is_empty()) else {
+                        continue;
+                    };
+                    let Some(amount) = Decimal::parse(amount).filter(|amount| amount.scale == 0) else {
+                        return;
+                    };
+                    Some(if indicator == "A" { -amount.units } else { amount.units })
+                }
+                _ => continue,
+            };
+            // Totals too large to add up are left to the element length checks
+            let Some(total) = amount.and_then(|amount| cents.checked_add(amount)) else {
+                return;
+            };
+            cents = total;
+        }
+        let expected = Decimal { units: cents, scale: 0 };
+        check_total(transaction, location, report, ("TDS", 1, true), expected, "total of IT1 lines and SAC charges in cents");
+    }
+}
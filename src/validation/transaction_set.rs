@@ -0,0 +1,168 @@
+use super::{
+    schema::element_reference, structure::StructureWalker, CodeListRegistry, DataType, ElementDefinition, IssueCode,
+    IssueLocation, SyntaxRule, TransactionSetSchema, Usage, ValidationReport,
+};
+use crate::{
+    error::EdiError,
+    models::{DatePeriod, Segment, Transaction},
+};
+
+/// Checks a transaction against its schema: segments must follow the
+/// schema's structure and satisfy their element definitions, with codes
+/// looked up in `code_lists` for `version`.
+///
+/// `location` identifies the transaction; issues narrow it to the segment
+/// and element concerned.
+pub fn validate_transaction(
+    schema: &TransactionSetSchema,
+    code_lists: &CodeListRegistry,
+    version: Option<&str>,
+    transaction: &Transaction,
+    location: &IssueLocation,
+    report: &mut ValidationReport,
+) {
+    let mut structure = StructureWalker::new(schema);
+    for (index, segment) in transaction.segments.iter().enumerate() {
+        let location = location.clone().segment(index, segment);
+        structure.step(segment, &location, report);
+        validate_segment(schema, code_lists, version, segment, &location, report);
+    }
+    // Whatever is still missing would have come after the last segment
+    let end = IssueLocation { segment_index: Some(transaction.segments.len()), ..location.clone() };
+    structure.finish(&end, report);
+}
+
+/// Checks the elements of `segment` against its definition in `schema`,
+/// syntax notes first. Identifier values must be in the element's own
+/// `codes` when it has any, or else in the registered code list for its
+/// data element. Segments the schema does not define are not checked.
+pub fn validate_segment(
+    schema: &TransactionSetSchema,
+    code_lists: &CodeListRegistry,
+    version: Option<&str>,
+    segment: &Segment,
+    location: &IssueLocation,
+    report: &mut ValidationReport,
+) {
+    let Some(definition) = schema.segment(&segment.id) else {
+        return;
+    };
+
+    if segment.elements.len() > definition.elements.len() {
+        report.error(
+            IssueCode::TooManyElements,
+            location.clone().element(definition.elements.len() + 1),
+            EdiError::TooManyElements {
+                segment: segment.id.clone(),
+                max: definition.elements.len(),
+                actual: segment.elements.len(),
+            },
+        );
+    }
+
+    let present = |position: usize| !segment.element(position).unwrap_or_default().is_empty();
+    for note in &definition.syntax {
+        if let Some(position) = note.violation(present) {
+            let code = match note.rule {
+                SyntaxRule::Exclusion => IssueCode::ExclusionViolated,
+                _ => IssueCode::ConditionalElementMissing,
+            };
+            report.error(
+                code,
+                location.clone().element(position),
+                EdiError::SyntaxNoteViolated {
+                    note: format!("{}{}", segment.id, note),
+                    reference: element_reference(&segment.id, position),
+                },
+            );
+        }
+    }
+
+    for (index, element) in definition.elements.iter().enumerate() {
+        let position = index + 1;
+        let reference = || element_reference(&segment.id, position);
+        let location = || location.clone().element(position);
+
+        let value = segment.element(position).unwrap_or_default();
+        if value.is_empty() {
+            if element.usage == Usage::Mandatory {
+                report.error(
+                    IssueCode::MissingElement,
+                    location(),
+                    EdiError::MissingElement { reference: reference() },
+                );
+            }
+            continue;
+        }
+        if element.usage == Usage::NotUsed {
+            report.error(
+                IssueCode::ElementNotUsed,
+                location(),
+                EdiError::ElementNotUsed { reference: reference() },
+            );
+            continue;
+        }
+
+        if !element.data_type.accepts(value) {
+            let value = value.to_string();
+            let (code, error) = match element.data_type {
+                DataType::Date => (IssueCode::InvalidDate, EdiError::InvalidDate { reference: reference(), value }),
+                DataType::Time => (IssueCode::InvalidTime, EdiError::InvalidTime { reference: reference(), value }),
+                data_type => (
+                    IssueCode::InvalidDataType,
+                    EdiError::InvalidDataType { reference: reference(), value, data_type: data_type.to_string() },
+                ),
+            };
+            report.error(code, location(), error);
+            continue;
+        }
+
+        if let Some(format) = element.format_element.and_then(|position| segment.element(position)) {
+            if DatePeriod::FORMATS.contains(&format) && DatePeriod::parse(format, value).is_none() {
+                report.error(
+                    IssueCode::InvalidDate,
+                    location(),
+                    EdiError::InvalidDate { reference: reference(), value: value.to_string() },
+                );
+                continue;
+            }
+        }
+
+        let length = element.data_type.length(value);
+        if let Some(min) = element.min_length.filter(|min| length < *min) {
+            report.error(
+                IssueCode::ElementTooShort,
+                location(),
+                EdiError::ElementTooShort { reference: reference(), value: value.to_string(), min },
+            );
+        } else if let Some(max) = element.max_length.filter(|max| length > *max) {
+            report.error(
+                IssueCode::ElementTooLong,
+                location(),
+                EdiError::ElementTooLong { reference: reference(), value: value.to_string(), max },
+            );
+        } else if !code_allowed(element, code_lists, version, value) {
+            report.error(
+                IssueCode::InvalidCode,
+                location(),
+                EdiError::InvalidCodeValue { reference: reference(), value: value.to_string() },
+            );
+        }
+    }
+}
+
+fn code_allowed(
+    element: &ElementDefinition,
+    code_lists: &CodeListRegistry,
+    version: Option<&str>,
+    value: &str,
+) -> bool {
+    if !element.codes.is_empty() {
+        return element.codes.iter().any(|code| code == value);
+    }
+    element
+        .id
+        .as_deref()
+        .and_then(|id| code_lists.contains(id, version, value))
+        .unwrap_or(true)
+}
@@ -0,0 +1,310 @@
+use std::sync::Arc;
+
+use super::{
+    validate_control_numbers, InvoiceTotals, PartnerProfile, PurchaseOrderTotals, validate_counts, validate_transaction, CodeListRegistry, IssueCode,
+    IssueLocation, SchemaRegistry, TransactionSetSchema, ValidationReport,
+};
+use crate::{
+    error::EdiError,
+    models::{FunctionalGroup, InterchangeControl, Segment, Transaction},
+};
+
+/// A rule for one kind of segment, run alongside the schema checks.
+///
+/// `location` already points at the segment; narrow it with
+/// [`IssueLocation::element`] when an issue concerns a single element.
+/// Closures with the same signature implement the trait.
+pub trait SegmentValidator: Send + Sync {
+    fn validate(&self, segment: &Segment, location: &IssueLocation, report: &mut ValidationReport);
+}
+
+impl<F> SegmentValidator for F
+where
+    F: Fn(&Segment, &IssueLocation, &mut ValidationReport) + Send + Sync,
+{
+    fn validate(&self, segment: &Segment, location: &IssueLocation, report: &mut ValidationReport) {
+        self(segment, location, report)
+    }
+}
+
+/// A rule spanning a whole transaction, such as totals that must match its
+/// line items. `location` points at the transaction.
+pub trait TransactionValidator: Send + Sync {
+    fn validate(&self, transaction: &Transaction, location: &IssueLocation, report: &mut ValidationReport);
+}
+
+impl<F> TransactionValidator for F
+where
+    F: Fn(&Transaction, &IssueLocation, &mut ValidationReport) + Send + Sync,
+{
+    fn validate(&self, transaction: &Transaction, location: &IssueLocation, report: &mut ValidationReport) {
+        self(transaction, location, report)
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct SegmentRule {
+    /// ST01 the rule is limited to, or `None` for every transaction set.
+    pub(super) transaction_set: Option<String>,
+    pub(super) segment_id: String,
+    pub(super) validator: Arc<dyn SegmentValidator>,
+}
+
+#[derive(Clone)]
+pub(super) struct TransactionRule {
+    pub(super) transaction_set: Option<String>,
+    pub(super) validator: Arc<dyn TransactionValidator>,
+}
+
+/// Everything an interchange is validated against: the envelope rules,
+/// transaction set schemas, code lists, any registered validators and the
+/// trading partner profiles laid over them.
+///
+/// [`Validator::new`], like the default validator, includes one business
+/// rule per built-in transaction set: the 850 and 810 control totals,
+/// checked by [`PurchaseOrderTotals`] and [`InvoiceTotals`].
+/// [`Validator::without_business_rules`] starts without them.
+///
+/// ```rust
+/// use edi_parser::{EdiError, IssueCode, IssueLocation, Segment, ValidationReport, Validator};
+///
+/// let validator = Validator::default().with_segment_validator(
+///     "850",
+///     "MY_SEG",
+///     |segment: &Segment, location: &IssueLocation, report: &mut ValidationReport| {
+///         if segment.element(2).is_none() {
+///             report.error(
+///                 IssueCode::InvalidSegment,
+///                 location.clone().element(2),
+///                 EdiError::ValidationError("MY_SEG requires at least 2 elements".to_string()),
+///             );
+///         }
+///     },
+/// );
+/// ```
+#[derive(Clone)]
+pub struct Validator {
+    schemas: SchemaRegistry,
+    code_lists: CodeListRegistry,
+    segment_rules: Vec<SegmentRule>,
+    transaction_rules: Vec<TransactionRule>,
+    profiles: Vec<PartnerProfile>,
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Validator {
+    /// The standard schemas and code lists, with the 850 and 810 control
+    /// total checks.
+    pub fn new() -> Self {
+        Self::without_business_rules()
+            .with_transaction_validator("850", PurchaseOrderTotals)
+            .with_transaction_validator("810", InvoiceTotals)
+    }
+
+    /// The standard schemas and code lists, without any business rules.
+    pub fn without_business_rules() -> Self {
+        Self {
+            schemas: SchemaRegistry::default(),
+            code_lists: CodeListRegistry::default(),
+            segment_rules: Vec::new(),
+            transaction_rules: Vec::new(),
+            profiles: Vec::new(),
+        }
+    }
+
+    /// Replaces the schemas transactions are checked against.
+    pub fn with_schemas(mut self, schemas: SchemaRegistry) -> Self {
+        self.schemas = schemas;
+        self
+    }
+
+    /// Adds a schema, replacing any for the same transaction set and version.
+    pub fn with_schema(mut self, schema: TransactionSetSchema) -> Self {
+        self.schemas.register(schema);
+        self
+    }
+
+    /// Replaces the code lists identifier elements are checked against.
+    pub fn with_code_lists(mut self, code_lists: CodeListRegistry) -> Self {
+        self.code_lists = code_lists;
+        self
+    }
+
+    /// Runs `validator` on every `segment_id` segment of `transaction_set`
+    /// transactions.
+    pub fn with_segment_validator(
+        mut self,
+        transaction_set: &str,
+        segment_id: &str,
+        validator: impl SegmentValidator + 'static,
+    ) -> Self {
+        self.segment_rules.push(SegmentRule {
+            transaction_set: Some(transaction_set.to_string()),
+            segment_id: segment_id.to_string(),
+            validator: Arc::new(validator),
+        });
+        self
+    }
+
+    /// Runs `validator` on every `segment_id` segment of any transaction set.
+    pub fn with_segment_validator_for_all(
+        mut self,
+        segment_id: &str,
+        validator: impl SegmentValidator + 'static,
+    ) -> Self {
+        self.segment_rules.push(SegmentRule {
+            transaction_set: None,
+            segment_id: segment_id.to_string(),
+            validator: Arc::new(validator),
+        });
+        self
+    }
+
+    /// Runs `validator` on every `transaction_set` transaction.
+    pub fn with_transaction_validator(
+        mut self,
+        transaction_set: &str,
+        validator: impl TransactionValidator + 'static,
+    ) -> Self {
+        self.transaction_rules.push(TransactionRule {
+            transaction_set: Some(transaction_set.to_string()),
+            validator: Arc::new(validator),
+        });
+        self
+    }
+
+    /// Runs `validator` on every transaction, whatever its set.
+    pub fn with_transaction_validator_for_all(mut self, validator: impl TransactionValidator + 'static) -> Self {
+        self.transaction_rules.push(TransactionRule { transaction_set: None, validator: Arc::new(validator) });
+        self
+    }
+
+    /// Adds a trading partner profile. Profiles are tried in the order
+    /// they were added.
+    pub fn with_profile(mut self, profile: PartnerProfile) -> Self {
+        self.profiles.push(profile);
+        self
+    }
+
+    pub fn profiles(&self) -> &[PartnerProfile] {
+        &self.profiles
+    }
+
+    /// The profile `group` is validated against: the first selected by
+    /// GS02/GS03, or else the first selected by the interchange's
+    /// ISA06/ISA08.
+    pub fn profile_for(&self, interchange: &InterchangeControl, group: &FunctionalGroup) -> Option<&PartnerProfile> {
+        self.profile_index(interchange, group).map(|index| &self.profiles[index])
+    }
+
+    fn profile_index(&self, interchange: &InterchangeControl, group: &FunctionalGroup) -> Option<usize> {
+        let selected = |by_group| self.profiles.iter().position(|profile| profile.selects(interchange, group, by_group));
+        selected(true).or_else(|| selected(false))
+    }
+
+    pub fn schemas(&self) -> &SchemaRegistry {
+        &self.schemas
+    }
+
+    pub fn code_lists(&self) -> &CodeListRegistry {
+        &self.code_lists
+    }
+
+    /// Collects every issue in `interchange`: envelope issues recorded
+    /// while parsing, the envelope and its control numbers and counts, and
+    /// each transaction, checked against its partner's profile if one
+    /// applies.
+    pub fn validate_report(&self, interchange: &InterchangeControl) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        for issue in &interchange.envelope_issues {
+            report.push(issue.clone());
+        }
+        let envelope = || IssueLocation {
+            position: interchange.isa_segment.position,
+            ..IssueLocation::default()
+        };
+
+        if interchange.isa_segment.id != "ISA" {
+            report.error(
+                IssueCode::InvalidEnvelope,
+                envelope(),
+                EdiError::MissingRequiredSegment("ISA".to_string()),
+            );
+        }
+
+        if let Some(iea) = &interchange.iea_segment {
+            if iea.id != "IEA" {
+                report.error(
+                    IssueCode::InvalidEnvelope,
+                    IssueLocation { position: iea.position, ..IssueLocation::default() },
+                    EdiError::InvalidControlStructure,
+                );
+            }
+        }
+
+        validate_control_numbers(interchange, &mut report);
+        validate_counts(interchange, &mut report);
+
+        // Overlays are built once per profile, for the first group it applies to
+        let mut overlays: Vec<Option<Validator>> = vec![None; self.profiles.len()];
+        for (group_index, fg) in interchange.functional_groups.iter().enumerate() {
+            let group = IssueLocation::group(group_index, fg);
+            let version = fg.gs_segment.element(8);
+            let validator = match self.profile_index(interchange, fg) {
+                Some(index) => overlays[index].get_or_insert_with(|| self.profiles[index].overlay(self)),
+                None => self,
+            };
+            for (transaction_index, transaction) in fg.transactions.iter().enumerate() {
+                let location = group.clone().transaction(transaction_index, transaction);
+                validator.validate_transaction(transaction, version, &location, &mut report);
+            }
+        }
+
+        report
+    }
+
+    pub(super) fn add_rules(&mut self, segment_rules: &[SegmentRule], transaction_rules: &[TransactionRule]) {
+        self.segment_rules.extend_from_slice(segment_rules);
+        self.transaction_rules.extend_from_slice(transaction_rules);
+    }
+
+    /// Fails with the first error `validate_report` finds.
+    pub fn validate(&self, interchange: &InterchangeControl) -> Result<(), EdiError> {
+        self.validate_report(interchange).into_result()
+    }
+
+    /// Checks one transaction of a group in `version` against the schema
+    /// for its set, without applying any profile, then runs the registered
+    /// segment and transaction validators that apply to it.
+    pub fn validate_transaction(
+        &self,
+        transaction: &Transaction,
+        version: Option<&str>,
+        location: &IssueLocation,
+        report: &mut ValidationReport,
+    ) {
+        let transaction_set = transaction.transaction_set_id.as_str();
+        if let Some(schema) = self.schemas.get(transaction_set, version) {
+            validate_transaction(schema, &self.code_lists, version, transaction, location, report);
+        }
+
+        let applies = |rule_set: &Option<String>| rule_set.as_deref().is_none_or(|set| set == transaction_set);
+        for (index, segment) in transaction.segments.iter().enumerate() {
+            let rules = self
+                .segment_rules
+                .iter()
+                .filter(|rule| rule.segment_id == segment.id && applies(&rule.transaction_set));
+            for rule in rules {
+                rule.validator.validate(segment, &location.clone().segment(index, segment), report);
+            }
+        }
+        for rule in self.transaction_rules.iter().filter(|rule| applies(&rule.transaction_set)) {
+            rule.validator.validate(transaction, location, report);
+        }
+    }
+}
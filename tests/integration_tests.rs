@@ -1,17 +1,44 @@
 use edi_parser::{
     X12Parser,
     EdiParser,
-    InterchangeControl, 
-    Segment, 
-    Transaction,
     EdiError,
+    Delimiters,
+    IsaHeader,
+    TransactionType,
+    StreamedTransaction,
+    X12Event,
+    EventKind,
+    Encoding,
+    ByteSegment,
+    SourcePosition,
+    IssueCode,
+    Severity,
+    ParseMode,
+    ParserOptions,
+    SchemaRegistry,
+    CodeList,
+    CodeListRegistry,
+    Segment,
+    DatePeriod,
+    SyntaxNote,
+    SyntaxRule,
+    TransactionSetSchema,
+    IssueLocation,
+    ValidationReport,
+    Transaction,
+    Validator,
+    SegmentValidator,
+    PartnerProfile,
+    PartnerSelector,
+    Usage,
+    FunctionalAcknowledgment,
 };
 use std::fs;
 
 #[test]
 fn test_basic_x12_parsing() {
     let parser = X12Parser::default();
-    let input = "ISA*00*          *00*          *01*SENDERID     *01*RECEIVERID   *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~SE*4*0001~GE*1*1~IEA*1*000000001~";
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~SE*4*0001~GE*1*1~IEA*1*000000001~";
     
     let result = parser.parse(input);
     assert!(result.is_ok());
@@ -40,8 +67,8 @@ fn test_810_invoice_parsing() {
     assert_eq!(interchange.isa_segment.elements[6], "01"); // Receiver qualifier
     assert_eq!(interchange.isa_segment.elements[7], "RECEIVERID"); // Receiver ID (trimmed)
     
-    assert!(interchange.functional_groups.len() > 0, "No functional groups found");
-    assert!(interchange.functional_groups[0].transactions.len() > 0, "No transactions found");
+    assert!(!interchange.functional_groups.is_empty(), "No functional groups found");
+    assert!(!interchange.functional_groups[0].transactions.is_empty(), "No transactions found");
     
     let transaction = &interchange.functional_groups[0].transactions[0];
     assert_eq!(transaction.transaction_set_id, "810");
@@ -61,8 +88,8 @@ fn test_850_purchase_order_parsing() {
     let interchange = result.unwrap();
     
     // Basic structure validation
-    assert!(interchange.functional_groups.len() > 0, "No functional groups found");
-    assert!(interchange.functional_groups[0].transactions.len() > 0, "No transactions found");
+    assert!(!interchange.functional_groups.is_empty(), "No functional groups found");
+    assert!(!interchange.functional_groups[0].transactions.is_empty(), "No transactions found");
     
     let transaction = &interchange.functional_groups[0].transactions[0];
     assert_eq!(transaction.transaction_set_id, "850");
@@ -88,7 +115,7 @@ fn test_invalid_input() {
 #[test]
 fn test_validation() {
     let parser = X12Parser::default();
-    let input = "ISA*00*          *00*          *01*SENDERID     *01*RECEIVERID   *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~SE*4*0001~GE*1*1~IEA*1*000000001~";
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~SE*3*0001~GE*1*1~IEA*1*000000001~";
     
     let interchange = parser.parse(input).unwrap();
     let validation_result = parser.validate(&interchange);
@@ -109,8 +136,8 @@ fn test_850_extended_parsing() {
     let interchange = result.unwrap();
 
     // Basic structure validation
-    assert!(interchange.functional_groups.len() > 0, "No functional groups found");
-    assert!(interchange.functional_groups[0].transactions.len() > 0, "No transactions found");
+    assert!(!interchange.functional_groups.is_empty(), "No functional groups found");
+    assert!(!interchange.functional_groups[0].transactions.is_empty(), "No transactions found");
 
     let transaction = &interchange.functional_groups[0].transactions[0];
     assert_eq!(transaction.transaction_set_id, "850");
@@ -135,11 +162,1218 @@ fn test_850_extended_parsing() {
 fn test_invalid_850_segment() {
     let parser = X12Parser::default();
     // Create an 850 with invalid BEG segment (missing required elements)
-    let input = "ISA*00*          *00*          *01*BUYERID      *01*SELLERID     *230101*1300*U*00401*000000002*0*T*>~GS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~ST*850*0001~BEG*00~SE*2*0001~GE*1*2~IEA*1*000000002~";
+    let input = "ISA*00*          *00*          *01*BUYERID        *01*SELLERID       *230101*1300*U*00401*000000002*0*T*>~GS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~ST*850*0001~BEG*00~SE*2*0001~GE*1*2~IEA*1*000000002~";
 
     let interchange = parser.parse(input).unwrap();
     let validation_result = parser.validate(&interchange);
 
     // Should fail validation due to incomplete BEG segment
     assert!(validation_result.is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_validation_report_collects_all_issues() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*BUYERID        *01*SELLERID       *230101*1300*U*00401*000000002*0*T*>~GS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~ST*850*0001~BEG*99*SA*PO-1~PO1*1*TEN*EA*9.99~SE*4*0001~ST*850*0002~PO1*1*5*EA*9.99~SE*3*0002~GE*2*2~IEA*1*000000002~";
+
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+
+    let found: Vec<_> = report.issues().iter()
+        .map(|issue| (
+            issue.code,
+            issue.location.transaction_control_number.as_deref(),
+            issue.location.segment_index,
+        ))
+        .collect();
+    assert_eq!(found, vec![
+        (IssueCode::InvalidCode, Some("0001"), Some(1)),
+        (IssueCode::InvalidDataType, Some("0001"), Some(2)),
+        (IssueCode::MissingSegment, Some("0002"), Some(1)),
+    ]);
+    assert!(report.errors().all(|issue| issue.severity == Severity::Error));
+    assert!(!report.is_valid());
+
+    let beg = &report.issues()[0];
+    assert_eq!(beg.location.group_control_number.as_deref(), Some("2"));
+    assert_eq!(beg.location.segment_id.as_deref(), Some("BEG"));
+    assert_eq!(beg.location.element, Some(1));
+    assert_eq!(beg.error, EdiError::InvalidCodeValue { reference: "BEG01".to_string(), value: "99".to_string() });
+    assert_eq!(beg.location.position.unwrap().byte_offset, input.find("BEG").unwrap());
+
+    // The convenience wrapper still reports the first error
+    assert_eq!(parser.validate(&interchange).unwrap_err(), beg.to_error());
+}
+
+#[test]
+fn test_control_number_mismatches() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~SE*3*0002~GE*1*7~IEA*1*000000009~";
+
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+    let mismatches: Vec<_> = report.issues().iter()
+        .filter(|issue| issue.code == IssueCode::ControlNumberMismatch)
+        .collect();
+
+    assert_eq!(mismatches.len(), 3);
+    assert_eq!(mismatches[0].error, EdiError::InterchangeControlNumberMismatch {
+        isa13: "000000001".to_string(),
+        iea02: "000000009".to_string(),
+    });
+    assert_eq!(mismatches[1].error, EdiError::GroupControlNumberMismatch {
+        gs06: "1".to_string(),
+        ge02: "7".to_string(),
+    });
+    assert_eq!(mismatches[2].error, EdiError::TransactionControlNumberMismatch {
+        st02: "0001".to_string(),
+        se02: "0002".to_string(),
+    });
+    assert_eq!(mismatches[2].location.segment_id.as_deref(), Some("SE"));
+    assert_eq!(mismatches[2].location.element, Some(2));
+    assert!(parser.validate(&interchange).is_err());
+}
+
+#[test]
+fn test_envelope_count_mismatches() {
+    // The extended sample originally shipped with SE*20 for its 23 segments
+    let content = fs::read_to_string("tests/test_files/sample_850_extended.edi").unwrap()
+        .replace("SE*23*0001~", "SE*20*0001~")
+        .replace("GE*1*2~", "GE*2*2~")
+        .replace("IEA*1*", "IEA*0*");
+
+    let parser = X12Parser::default();
+    let interchange = parser.parse(&content).unwrap();
+    let report = parser.validate_report(&interchange);
+    let counts: Vec<_> = report.issues().iter()
+        .filter(|issue| issue.code == IssueCode::CountMismatch)
+        .map(|issue| (issue.error.clone(), issue.location.segment_id.as_deref(), issue.location.element))
+        .collect();
+
+    assert_eq!(counts, vec![
+        (EdiError::GroupCountMismatch { iea01: "0".to_string(), actual: 1 }, Some("IEA"), Some(1)),
+        (EdiError::TransactionCountMismatch { ge01: "2".to_string(), actual: 1 }, Some("GE"), Some(1)),
+        (EdiError::SegmentCountMismatch { se01: "20".to_string(), actual: 23 }, Some("SE"), Some(1)),
+    ]);
+    assert_eq!(report.issues()[2].location.transaction_control_number.as_deref(), Some("0001"));
+}
+
+#[test]
+fn test_strict_mode_rejects_broken_envelopes() {
+    let parser = X12Parser::default();
+    let isa = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~";
+    let gs = "GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~";
+
+    let st_before_se = format!("{isa}{gs}ST*810*0001~BIG*20230101*INV-001~ST*810*0002~BIG*20230101*INV-002~SE*3*0002~GE*2*1~IEA*1*000000001~");
+    let error = parser.parse(&st_before_se).unwrap_err();
+    assert_eq!(error.root(), &EdiError::UnexpectedSegment {
+        id: "ST".to_string(),
+        context: "before SE of transaction 0001".to_string(),
+    });
+    assert_eq!(error.position().unwrap().segment_index, 4);
+
+    let orphan = format!("{isa}{gs}REF*IA*123~ST*810*0001~BIG*20230101*INV-001~SE*3*0001~GE*1*1~IEA*1*000000001~");
+    assert!(matches!(parser.parse(&orphan).unwrap_err().root(), EdiError::UnexpectedSegment { id, .. } if id == "REF"));
+
+    let unterminated_group = format!("{isa}{gs}ST*810*0001~BIG*20230101*INV-001~SE*3*0001~IEA*1*000000001~");
+    assert_eq!(parser.parse(&unterminated_group).unwrap_err().root(), &EdiError::MissingTrailer {
+        trailer: "GE".to_string(),
+        control_number: "1".to_string(),
+    });
+
+    let trailer_out_of_order = format!("{isa}{gs}ST*810*0001~BIG*20230101*INV-001~GE*1*1~SE*3*0001~IEA*1*000000001~");
+    assert_eq!(parser.parse(&trailer_out_of_order).unwrap_err().root(), &EdiError::MissingTrailer {
+        trailer: "SE".to_string(),
+        control_number: "0001".to_string(),
+    });
+
+    let truncated = format!("{isa}{gs}ST*810*0001~BIG*20230101*INV-001~");
+    let error = parser.parse(&truncated).unwrap_err();
+    assert_eq!(error.root(), &EdiError::MissingTrailer {
+        trailer: "SE".to_string(),
+        control_number: "0001".to_string(),
+    });
+    assert_eq!(error.position().unwrap().byte_offset, truncated.find("ST*").unwrap());
+}
+
+#[test]
+fn test_lenient_mode_records_envelope_issues() {
+    let parser = X12Parser::default().with_mode(ParseMode::Lenient);
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~REF*IA*123~ST*810*0001~BIG*20230101*INV-001~ST*810*0002~BIG*20230101*INV-002~SE*3*0002~SE*3*0002~";
+
+    let interchange = parser.parse(input).unwrap();
+    assert_eq!(interchange.functional_groups[0].transactions.len(), 2);
+    assert!(interchange.functional_groups[0].ge_segment.is_none());
+    assert!(interchange.iea_segment.is_none());
+
+    let found: Vec<_> = interchange.envelope_issues.iter()
+        .map(|issue| (issue.code, issue.location.segment_id.as_deref().unwrap()))
+        .collect();
+    assert_eq!(found, vec![
+        (IssueCode::UnexpectedSegment, "REF"),
+        (IssueCode::UnexpectedSegment, "ST"),
+        (IssueCode::UnexpectedSegment, "SE"),
+        (IssueCode::MissingTrailer, "GS"),
+        (IssueCode::MissingTrailer, "ISA"),
+    ]);
+    assert_eq!(interchange.envelope_issues[1].location.transaction_control_number.as_deref(), Some("0001"));
+
+    let report = parser.validate_report(&interchange);
+    assert_eq!(&report.issues()[..5], &interchange.envelope_issues[..]);
+    assert!(parser.validate(&interchange).is_err());
+}
+
+#[test]
+fn test_segment_after_iea() {
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~SE*3*0001~GE*1*1~IEA*1*000000001~REF*IA*123~";
+    let expected = EdiError::UnexpectedSegment { id: "REF".to_string(), context: "outside any transaction".to_string() };
+
+    // Recorded on the interchange it follows in lenient mode
+    let interchange = X12Parser::default().with_mode(ParseMode::Lenient).parse(input).unwrap();
+    assert!(interchange.iea_segment.is_some());
+    assert_eq!(interchange.envelope_issues.len(), 1);
+    let issue = &interchange.envelope_issues[0];
+    assert_eq!(issue.code, IssueCode::UnexpectedSegment);
+    assert_eq!(issue.error, expected);
+    assert_eq!(issue.location.position.unwrap().byte_offset, input.find("REF").unwrap());
+
+    let error = X12Parser::default().parse(input).unwrap_err();
+    assert_eq!(error.root(), &expected);
+    assert_eq!(error.position().unwrap().byte_offset, input.find("REF").unwrap());
+}
+
+#[test]
+fn test_parser_options() {
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001*~N1*ST*ACME Corp  ~SE*4*0001~GE*1*1~IEA*1*000000001~";
+
+    // Defaults: trim element values and drop a trailing element separator
+    let interchange = X12Parser::default().parse(input).unwrap();
+    let segments = &interchange.functional_groups[0].transactions[0].segments;
+    assert_eq!(segments[1].elements, vec!["20230101", "INV-001"]);
+    assert_eq!(segments[2].elements[1], "ACME Corp");
+
+    let untrimmed = X12Parser::default()
+        .with_options(ParserOptions::default().with_trim_whitespace(false))
+        .parse(input)
+        .unwrap();
+    assert_eq!(untrimmed.functional_groups[0].transactions[0].segments[2].elements[1], "ACME Corp  ");
+
+    let no_trailing = ParserOptions::strict().with_trailing_separator(false);
+    let error = X12Parser::default().with_options(no_trailing).parse(input).unwrap_err();
+    assert_eq!(error.root(), &EdiError::TrailingElementSeparator("BIG*20230101*INV-001*".to_string()));
+    assert_eq!(error.element(), Some(3));
+
+    let lenient = X12Parser::default().with_options(no_trailing.with_mode(ParseMode::Lenient));
+    let interchange = lenient.parse(input).unwrap();
+    assert_eq!(interchange.envelope_issues.len(), 1);
+    assert_eq!(interchange.envelope_issues[0].code, IssueCode::TrailingSeparator);
+    assert_eq!(interchange.envelope_issues[0].location.segment_index, Some(1));
+
+    let too_long = ParserOptions::default().with_max_segment_length(30);
+    assert_eq!(
+        X12Parser::default().with_options(too_long).parse(input).unwrap_err().root(),
+        &EdiError::SegmentTooLong { max: 30, actual: 105 }
+    );
+
+    let too_many = ParserOptions::lenient().with_max_segment_count(5);
+    let error = X12Parser::default().with_options(too_many).parse(input).unwrap_err();
+    assert_eq!(error.root(), &EdiError::TooManySegments { max: 5 });
+    assert_eq!(error.position().unwrap().segment_index, 5);
+}
+
+#[test]
+fn test_delimiters_detected_from_isa() {
+    let parser = X12Parser::default();
+    let input = "ISA|00|          |00|          |01|SENDERID       |01|RECEIVERID     |230101|1253|U|00401|000000001|0|T|:\nGS|IN|SENDERID|RECEIVERID|20230101|1253|1|X|004010\nST|810|0001\nBIG|20230101|INV-001\nSE|3|0001\nGE|1|1\nIEA|1|000000001\n";
+
+    let interchange = parser.parse(input).expect("pipe-delimited interchange should parse");
+    assert_eq!(interchange.isa_segment.elements[5], "SENDERID");
+    assert_eq!(interchange.isa_segment.elements[15], ":");
+
+    let transaction = &interchange.functional_groups[0].transactions[0];
+    assert_eq!(transaction.transaction_set_id, "810");
+    assert_eq!(transaction.segments[1].elements, vec!["20230101", "INV-001"]);
+
+    let delimiters = Delimiters::from_isa(input).unwrap();
+    assert_eq!(delimiters.element, '|');
+    assert_eq!(delimiters.segment, '\n');
+    assert_eq!(delimiters.component, ':');
+}
+
+#[test]
+fn test_truncated_isa_rejected() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*SENDERID*01*RECEIVERID*230101*1253*U*00401*000000001*0*T*>~GS*IN~";
+
+    let error = parser.parse(input).unwrap_err();
+    assert!(matches!(error.root(), EdiError::InvalidIsaField { .. }));
+    assert_eq!(error.position().map(|p| p.segment_index), Some(0));
+    assert_eq!(error.element(), Some(6));
+}
+
+#[test]
+fn test_isa_header_keeps_padding() {
+    let content = fs::read_to_string("tests/test_files/sample_810.edi")
+        .expect("Failed to read test file");
+
+    let parser = X12Parser::default();
+    let interchange = parser.parse(&content).unwrap();
+    let header = interchange.isa_header();
+
+    assert_eq!(header.raw(6), Some("SENDERID       "));
+    assert_eq!(header.value(6), Some("SENDERID"));
+    assert_eq!(header.raw(2), Some("          "));
+    assert_eq!(header.receiver_id(), "RECEIVERID");
+    assert_eq!(header.control_number(), "000000001");
+    assert_eq!(header.to_string(), content.lines().next().unwrap());
+}
+
+#[test]
+fn test_isa_field_width_errors() {
+    let input = "ISA*00*          *00*          *01*SENDERID     *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~";
+
+    assert_eq!(
+        IsaHeader::parse(input),
+        Err(EdiError::InvalidIsaField {
+            field: "ISA06".to_string(),
+            expected: 15,
+            actual: 13,
+        })
+    );
+}
+
+#[test]
+fn test_5010_repetition_separator() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *ZZ*SUBMITTERID    *ZZ*RECEIVERID     *230101*1253*^*00501*000000005*0*T*:~GS*HC*SUBMITTERID*RECEIVERID*20230101*1253*5*X*005010X222A1~ST*837*0001*005010X222A1~HI*ABK:J449^ABF:E119^ABF:I10~SE*3*0001~GE*1*5~IEA*1*000000005~";
+
+    let interchange = parser.parse(input).unwrap();
+    assert_eq!(interchange.isa_header().delimiters.repetition, Some('^'));
+
+    let hi = &interchange.functional_groups[0].transactions[0].segments[1];
+    assert_eq!(hi.id, "HI");
+    assert_eq!(hi.repetitions(1), vec!["ABK:J449", "ABF:E119", "ABF:I10"]);
+    assert_eq!(hi.repetition(1, 2), Some("ABF:E119"));
+    assert_eq!(hi.repetition(1, 4), None);
+    assert!(hi.repetitions(2).is_empty());
+}
+
+#[test]
+fn test_4010_has_no_repetition_separator() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV^001~SE*3*0001~GE*1*1~IEA*1*000000001~";
+
+    let interchange = parser.parse(input).unwrap();
+    assert_eq!(interchange.isa_header().delimiters.repetition, None);
+
+    let big = &interchange.functional_groups[0].transactions[0].segments[1];
+    assert_eq!(big.repetitions(2), vec!["INV^001"]);
+}
+
+#[test]
+fn test_composite_elements() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *ZZ*SUBMITTERID    *ZZ*RECEIVERID     *230101*1253*^*00501*000000006*0*T*>~GS*HC*SUBMITTERID*RECEIVERID*20230101*1253*6*X*005010X222A1~ST*837*0001*005010X222A1~SV1*HC>99213>25*125*UN*1~HI*ABK>J449^ABF>E119~SE*4*0001~GE*1*6~IEA*1*000000006~";
+
+    let interchange = parser.parse(input).unwrap();
+    let segments = &interchange.functional_groups[0].transactions[0].segments;
+
+    let sv1 = &segments[1];
+    assert_eq!(sv1.elements[0], "HC>99213>25");
+    assert_eq!(sv1.component(1, 2), Some("99213"));
+    assert_eq!(sv1.components(1), vec!["HC", "99213", "25"]);
+    assert!(sv1.composite(1).unwrap().is_composite());
+    assert!(!sv1.composite(2).unwrap().is_composite());
+    assert_eq!(sv1.component(2, 1), Some("125"));
+    assert_eq!(sv1.component(1, 4), None);
+
+    let hi = &segments[2];
+    let codes: Vec<&str> = hi.occurrences(1).iter().filter_map(|e| e.component(2)).collect();
+    assert_eq!(codes, vec!["J449", "E119"]);
+
+    let target = Delimiters {
+        element: '|',
+        segment: '\n',
+        component: ':',
+        repetition: Some('~'),
+    };
+    assert_eq!(sv1.to_edi(&target), "SV1|HC:99213:25|125|UN|1\n");
+    assert_eq!(hi.to_edi(&target), "HI|ABK:J449~ABF:E119\n");
+}
+
+#[test]
+fn test_serialize_round_trip() {
+    let content = fs::read_to_string("tests/test_files/sample_850_extended.edi")
+        .expect("Failed to read extended test file");
+
+    let parser = X12Parser::default();
+    let interchange = parser.parse(&content).unwrap();
+
+    assert_eq!(interchange.to_edi(), content.replace('\n', ""));
+}
+
+#[test]
+fn test_borrowed_parsing() {
+    let content = fs::read_to_string("tests/test_files/sample_850.edi")
+        .expect("Failed to read test file");
+
+    let parser = X12Parser::default();
+    let borrowed = parser.parse_borrowed(&content).unwrap();
+
+    let transaction = &borrowed.functional_groups[0].transactions[0];
+    assert_eq!(transaction.transaction_set_id, "850");
+    assert_eq!(transaction.transaction_type(), TransactionType::PurchaseOrder850);
+
+    let beg = transaction.segments[1];
+    assert_eq!(beg.id, "BEG");
+    assert_eq!(beg.element(3), Some("PO-001"));
+    assert_eq!(beg.element(4), Some(""));
+    assert_eq!(beg.element_count(), 5);
+
+    // Elements are slices of the original buffer
+    let po_number = beg.element(3).unwrap();
+    let range = content.as_bytes().as_ptr_range();
+    assert!(range.contains(&po_number.as_ptr()));
+
+    assert_eq!(borrowed.to_owned(), parser.parse(&content).unwrap());
+    assert_eq!(beg.to_owned().elements, vec!["00", "SA", "PO-001", "", "20230101"]);
+}
+
+#[test]
+fn test_streaming_transactions() {
+    let invoice = fs::read_to_string("tests/test_files/sample_810.edi").unwrap();
+    let order = fs::read_to_string("tests/test_files/sample_850_extended.edi").unwrap();
+    let input = format!("{}\n{}", invoice, order);
+
+    // A tiny buffer forces segments to span several reads
+    let reader = std::io::BufReader::with_capacity(16, input.as_bytes());
+    let parser = X12Parser::default();
+    let transactions: Vec<StreamedTransaction> = parser
+        .stream(reader)
+        .collect::<Result<_, _>>()
+        .expect("stream should parse");
+
+    assert_eq!(transactions.len(), 2);
+    assert_eq!(transactions[0].transaction.transaction_set_id, "810");
+    assert_eq!(transactions[0].isa_header.control_number(), "000000001");
+    assert_eq!(transactions[0].gs_segment.elements[0], "IN");
+    assert_eq!(transactions[1].transaction.transaction_set_id, "850");
+    assert_eq!(transactions[1].isa_header.sender_id(), "BUYERID");
+    assert_eq!(transactions[1].gs_segment.elements[0], "PO");
+
+    let parsed = parser.parse(&order).unwrap();
+    assert_eq!(transactions[1].transaction, parsed.functional_groups[0].transactions[0]);
+}
+
+#[test]
+fn test_streaming_reports_truncated_transaction() {
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~";
+
+    let parser = X12Parser::default();
+    let mut stream = parser.stream(input.as_bytes());
+
+    let error = stream.next().unwrap().unwrap_err();
+    assert_eq!(error.root(), &EdiError::MissingRequiredSegment("SE for transaction 0001".to_string()));
+    assert_eq!(error.position().unwrap().byte_offset, input.find("ST*").unwrap());
+    assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn test_streaming_reports_envelope_errors() {
+    let isa = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~";
+    let gs = "GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~";
+    let parser = X12Parser::default();
+
+    let cases = [
+        // A second ST before the SE of the first
+        (
+            format!("{}{}ST*810*0001~BIG*20230101*INV-001~ST*810*0002~", isa, gs),
+            "ST*810*0002",
+            EdiError::UnexpectedSegment { id: "ST".to_string(), context: "before SE of transaction 0001".to_string() },
+        ),
+        // A segment between the SE and the next ST
+        (
+            format!("{}{}ST*810*0001~SE*2*0001~BIG*20230101*INV-001~", isa, gs),
+            "BIG*",
+            EdiError::UnexpectedSegment { id: "BIG".to_string(), context: "outside any transaction".to_string() },
+        ),
+        // A transaction left open when its group ends
+        (
+            format!("{}{}ST*810*0001~BIG*20230101*INV-001~GE*1*1~", isa, gs),
+            "GE*",
+            EdiError::MissingTrailer { trailer: "SE".to_string(), control_number: "0001".to_string() },
+        ),
+        // A group left open when the interchange ends
+        (
+            format!("{}{}ST*810*0001~SE*2*0001~IEA*1*000000001~", isa, gs),
+            "IEA*",
+            EdiError::MissingTrailer { trailer: "GE".to_string(), control_number: "1".to_string() },
+        ),
+        // A new interchange before the IEA of the previous one
+        (
+            format!("{}{}ST*810*0001~SE*2*0001~GE*1*1~{}", isa, gs, isa.replace("000000001", "000000002")),
+            "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000002",
+            EdiError::UnexpectedSegment { id: "ISA".to_string(), context: "before IEA of interchange 000000001".to_string() },
+        ),
+    ];
+
+    for (input, found, expected) in cases {
+        let mut stream = parser.stream(input.as_bytes());
+        let results: Vec<_> = stream.by_ref().collect();
+        let error = results.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(error.root(), &expected, "{}", input);
+        assert_eq!(error.position().unwrap().byte_offset, input.find(found).unwrap(), "{}", input);
+        assert_eq!(stream.next(), None);
+
+        // The tree builder rejects the same input with the same error
+        assert_eq!(parser.parse(&input).unwrap_err().root(), &expected, "{}", input);
+    }
+}
+
+#[test]
+fn test_streaming_reports_unclosed_envelope_at_end_of_input() {
+    let isa = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~";
+    let parser = X12Parser::default();
+
+    let cases = [
+        (format!("{}GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~SE*2*0001~", isa), "GS*", "GE", "1"),
+        (format!("{}GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~SE*2*0001~GE*1*1~", isa), "ISA*", "IEA", "000000001"),
+    ];
+
+    for (input, header, trailer, control_number) in cases {
+        let expected = EdiError::MissingTrailer { trailer: trailer.to_string(), control_number: control_number.to_string() };
+        let mut stream = parser.stream(input.as_bytes());
+        assert_eq!(stream.next().unwrap().unwrap().transaction.control_number, "0001");
+
+        let error = stream.next().unwrap().unwrap_err();
+        assert_eq!(error.root(), &expected);
+        assert_eq!(error.position().unwrap().byte_offset, input.find(header).unwrap());
+        assert_eq!(stream.next(), None);
+
+        let error = parser.parse(&input).unwrap_err();
+        assert_eq!(error.root(), &expected);
+        assert_eq!(error.position().unwrap().byte_offset, input.find(header).unwrap());
+    }
+}
+
+#[test]
+fn test_streaming_honours_options_and_encoding() {
+    let ebcdic = fs::read("tests/test_files/sample_810_ebcdic.edi").unwrap();
+    let parser = X12Parser::default().with_encoding(Encoding::Ebcdic);
+    let streamed: Vec<StreamedTransaction> = parser.stream(&ebcdic[..]).collect::<Result<_, _>>().unwrap();
+    let parsed = parser.parse_bytes(&ebcdic).unwrap();
+    assert_eq!(streamed.len(), 1);
+    assert_eq!(streamed[0].transaction, parsed.functional_groups[0].transactions[0]);
+
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001*~N1*ST* ACME ~SE*4*0001~GE*1*1~IEA*1*000000001~";
+    let stream = |options: ParserOptions| X12Parser::default().with_options(options).stream(input.as_bytes()).collect::<Result<Vec<_>, _>>();
+
+    let streamed = stream(ParserOptions::default().with_trim_whitespace(false)).unwrap();
+    assert_eq!(streamed[0].transaction.segments[2].elements, vec!["ST", " ACME "]);
+
+    let error = stream(ParserOptions::default().with_trailing_separator(false)).unwrap_err();
+    assert_eq!(error.root(), &EdiError::TrailingElementSeparator("BIG*20230101*INV-001*".to_string()));
+    assert_eq!(error.position().unwrap().byte_offset, input.find("BIG").unwrap());
+
+    let error = stream(ParserOptions::default().with_max_segment_count(4)).unwrap_err();
+    assert_eq!(error.root(), &EdiError::TooManySegments { max: 4 });
+    assert_eq!(error.position().unwrap().byte_offset, input.find("N1").unwrap());
+
+    // Envelope problems still end the stream in lenient mode
+    let truncated = input.replace("SE*4*0001~", "");
+    let error = X12Parser::default()
+        .with_options(ParserOptions::lenient())
+        .stream(truncated.as_bytes())
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(error.root(), &EdiError::MissingTrailer { trailer: "SE".to_string(), control_number: "0001".to_string() });
+}
+
+#[test]
+fn test_parse_all_concatenated_interchanges() {
+    let invoice = fs::read_to_string("tests/test_files/sample_810.edi").unwrap();
+    let pipe_delimited = "ISA|00|          |00|          |01|SENDERID       |01|RECEIVERID     |230101|1253|U|00401|000000003|0|T|:\nGS|IN|SENDERID|RECEIVERID|20230101|1253|3|X|004010\nST|810|0003\nBIG|20230101|INV-003\nSE|3|0003\nGE|1|3\nIEA|1|000000003\n";
+    let order = fs::read_to_string("tests/test_files/sample_850.edi").unwrap();
+    let input = format!("{}\n{}{}", invoice, pipe_delimited, order);
+
+    let parser = X12Parser::default();
+    let interchanges = parser.parse_all(&input).expect("all interchanges should parse");
+
+    assert_eq!(interchanges.len(), 3);
+    let control_numbers: Vec<String> = interchanges.iter()
+        .map(|i| i.isa_header().control_number().to_string())
+        .collect();
+    assert_eq!(control_numbers, vec!["000000001", "000000003", "000000002"]);
+
+    for interchange in &interchanges {
+        let iea = interchange.iea_segment.as_ref().expect("each interchange keeps its own IEA");
+        assert_eq!(iea.elements[1], interchange.isa_header().control_number());
+        assert_eq!(interchange.functional_groups.len(), 1);
+        assert_eq!(interchange.functional_groups[0].transactions.len(), 1);
+    }
+
+    assert_eq!(interchanges[1].functional_groups[0].transactions[0].segments[1].elements[1], "INV-003");
+    assert_eq!(interchanges[0], parser.parse(&invoice).unwrap());
+}
+
+#[test]
+fn test_parse_rejects_second_interchange() {
+    let invoice = fs::read_to_string("tests/test_files/sample_810.edi").unwrap();
+    let order = fs::read_to_string("tests/test_files/sample_850.edi").unwrap();
+    let input = format!("{}\n{}", invoice, order);
+
+    let parser = X12Parser::default();
+    let error = parser.parse(&input).unwrap_err();
+    match error.root() {
+        EdiError::ParseError(offset, _) => assert_eq!(&input[*offset..*offset + 3], "ISA"),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    assert_eq!(error.position().unwrap().line, invoice.lines().count() + 1);
+}
+
+#[test]
+fn test_event_stream() {
+    let content = fs::read_to_string("tests/test_files/sample_810.edi").unwrap();
+    let parser = X12Parser::default();
+
+    let events: Vec<X12Event> = parser.events(&content)
+        .collect::<Result<_, _>>()
+        .expect("events should parse");
+
+    let kinds: Vec<EventKind> = events.iter().map(|e| e.kind).collect();
+    assert_eq!(kinds, vec![
+        EventKind::StartInterchange,
+        EventKind::StartGroup,
+        EventKind::StartTransaction,
+        EventKind::Segment,
+        EventKind::Segment,
+        EventKind::Segment,
+        EventKind::Segment,
+        EventKind::Segment,
+        EventKind::EndTransaction,
+        EventKind::EndGroup,
+        EventKind::EndInterchange,
+    ]);
+
+    let big = &events[3];
+    assert_eq!(big.segment.id, "BIG");
+    assert_eq!(big.position.segment_index, 3);
+    assert!(content[big.position.byte_offset..].starts_with("BIG*20230101"));
+    assert_eq!(big.segment.delimiters, Delimiters::default());
+}
+
+#[test]
+fn test_events_extract_po_numbers() {
+    let first = fs::read_to_string("tests/test_files/sample_850.edi").unwrap();
+    let second = fs::read_to_string("tests/test_files/sample_850_extended.edi").unwrap()
+        .replace("PO-001", "PO-002");
+    let input = format!("{}\n{}", first, second);
+
+    let parser = X12Parser::default();
+    let po_numbers: Vec<&str> = parser.events(&input)
+        .filter_map(Result::ok)
+        .filter(|event| event.segment.id == "BEG")
+        .filter_map(|event| event.segment.element(3))
+        .collect();
+
+    assert_eq!(po_numbers, vec!["PO-001", "PO-002"]);
+}
+
+#[test]
+fn test_parse_latin1_bytes() {
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~N1*ST*Soci\u{e9}t\u{e9} G\u{e9}n\u{e9}rale~SE*4*0001~GE*1*1~IEA*1*000000001~";
+    let latin1: Vec<u8> = input.chars().map(|c| c as u8).collect();
+
+    let parser = X12Parser::default().with_encoding(Encoding::Latin1);
+    let interchange = parser.parse_bytes(&latin1).unwrap();
+    let n1 = &interchange.functional_groups[0].transactions[0].segments[2];
+    assert_eq!(n1.elements[1], "Soci\u{e9}t\u{e9} G\u{e9}n\u{e9}rale");
+
+    // The same bytes are not valid UTF-8, and the offending byte is located
+    let offset = latin1.iter().position(|&b| b == 0xE9).unwrap();
+    assert_eq!(
+        X12Parser::default().parse_bytes(&latin1),
+        Err(EdiError::UndecodableData { encoding: "UTF-8".to_string(), offset })
+    );
+}
+
+#[test]
+fn test_parse_ebcdic_bytes() {
+    let ebcdic = fs::read("tests/test_files/sample_810_ebcdic.edi").unwrap();
+    let ascii = fs::read_to_string("tests/test_files/sample_810.edi").unwrap();
+
+    let parser = X12Parser::default().with_encoding(Encoding::Ebcdic);
+    let interchange = parser.parse_bytes(&ebcdic).unwrap();
+
+    assert_eq!(interchange, X12Parser::default().parse(&ascii).unwrap());
+}
+
+#[test]
+fn test_segment_positions() {
+    let content = fs::read_to_string("tests/test_files/sample_810.edi").unwrap();
+    let interchange = X12Parser::default().parse(&content).unwrap();
+
+    assert_eq!(interchange.isa_segment.position, Some(SourcePosition { segment_index: 0, byte_offset: 0, line: 1, column: 1 }));
+    let it1 = &interchange.functional_groups[0].transactions[0].segments[3];
+    let position = it1.position.unwrap();
+    assert_eq!(position.segment_index, 5);
+    assert_eq!((position.line, position.column), (6, 1));
+    assert!(content[position.byte_offset..].starts_with("IT1*"));
+    assert_eq!(interchange.iea_segment.unwrap().position.unwrap().line, 11);
+}
+
+#[test]
+fn test_validation_error_location() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*BUYERID        *01*SELLERID       *230101*1300*U*00401*000000002*0*T*>~\nGS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~\nST*850*0001~\nBEG*00~\nSE*3*0001~\nGE*1*2~\nIEA*1*000000002~";
+
+    let interchange = parser.parse(input).unwrap();
+    let error = parser.validate(&interchange).unwrap_err();
+
+    assert_eq!(error.root(), &EdiError::MissingElement { reference: "BEG02".to_string() });
+    assert_eq!(error.position().unwrap().line, 4);
+    assert_eq!(error.element(), Some(2));
+    let expected = format!("at segment 4, element 2 (line 4, column 1, byte {})", input.find("BEG").unwrap());
+    assert!(error.to_string().ends_with(&expected), "{}", error);
+}
+
+#[test]
+fn test_byte_positions_after_single_byte_decoding() {
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~N1*ST*Caf\u{e9} Soci\u{e9}t\u{e9}~SE*4*0001~GE*1*1~IEA*1*000000001~";
+    let latin1: Vec<u8> = input.chars().map(|c| c as u8).collect();
+
+    let interchange = X12Parser::default()
+        .with_encoding(Encoding::Latin1)
+        .parse_bytes(&latin1)
+        .unwrap();
+
+    let se = &interchange.functional_groups[0].transactions[0].segments[3];
+    let offset = se.position.unwrap().byte_offset;
+    assert_eq!(&latin1[offset..offset + 3], b"SE*");
+
+    // Envelope issues recorded by a lenient parse point into the input too
+    let truncated: Vec<u8> = input.replace("SE*4*0001~", "").chars().map(|c| c as u8).collect();
+    let interchange = X12Parser::default()
+        .with_encoding(Encoding::Latin1)
+        .with_options(ParserOptions::lenient())
+        .parse_bytes(&truncated)
+        .unwrap();
+
+    let issue = &interchange.envelope_issues[0];
+    assert_eq!(issue.code, IssueCode::MissingTrailer);
+    let offset = issue.location.position.unwrap().byte_offset;
+    assert_eq!(&truncated[offset..offset + 3], b"GE*");
+}
+
+#[test]
+fn test_byte_segments_decode_lazily() {
+    let mut input = b"ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~N1*ST*ACME ".to_vec();
+    let bad_offset = input.len();
+    input.extend_from_slice(b"\x81 Corp*92*12345~SE*4*0001~GE*1*1~IEA*1*000000001~");
+
+    let parser = X12Parser::default().with_encoding(Encoding::Windows1252);
+    let segments: Vec<ByteSegment> = parser.byte_segments(&input)
+        .collect::<Result<_, _>>()
+        .expect("splitting does not decode element data");
+    assert_eq!(segments.len(), 8);
+
+    let n1 = &segments[4];
+    assert_eq!(n1.id().unwrap(), "N1");
+    assert_eq!(n1.element(1).unwrap().as_deref(), Some("ST"));
+    assert_eq!(n1.element(4).unwrap().as_deref(), Some("12345"));
+    let error = n1.element(2).unwrap_err();
+    assert_eq!(
+        error.root(),
+        &EdiError::UndecodableData { encoding: "Windows-1252".to_string(), offset: bad_offset }
+    );
+    assert_eq!(error.position(), Some(n1.position()));
+    assert_eq!(error.element(), Some(2));
+    assert!(n1.to_owned().is_err());
+    assert_eq!(segments[3].to_owned().unwrap().elements, vec!["20230101", "INV-001"]);
+}
+
+#[test]
+fn test_runtime_schema() {
+    let schema = TransactionSetSchema::from_json(r#"{
+        "id": "810",
+        "version": "004010",
+        "segments": {
+            "BIG": {"elements": [
+                {"usage": "M", "type": "DT"},
+                {"usage": "M", "type": "AN"},
+                {"usage": "O", "type": "DT"},
+                {"usage": "M", "type": "AN"}
+            ]},
+            "N1": {"elements": [
+                {"usage": "M", "type": "ID", "codes": ["RI"]},
+                {"usage": "O", "type": "AN"}
+            ]}
+        },
+        "structure": [
+            {"id": "ST", "usage": "M", "max_use": 1},
+            {"id": "BIG", "usage": "M", "max_use": 1},
+            {"loop": "N1", "max_use": 200, "segments": [{"id": "N1", "usage": "O", "max_use": 1}]},
+            {"id": "TDS", "usage": "M", "max_use": 1},
+            {"id": "SE", "usage": "M", "max_use": 1}
+        ]
+    }"#).unwrap();
+    assert_eq!(schema.mandatory_segments(), vec!["ST", "BIG", "TDS", "SE"]);
+
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~N1*ST*ABC Company~SE*4*0001~GE*1*1~IEA*1*000000001~";
+    let interchange = X12Parser::default().parse(input).unwrap();
+
+    // The built-in schema accepts the invoice
+    assert!(X12Parser::default().validate(&interchange).is_ok());
+
+    let parser = X12Parser::default().with_schema(schema.clone());
+    let report = parser.validate_report(&interchange);
+    let found: Vec<_> = report.issues().iter()
+        .map(|issue| (issue.code, issue.location.to_string()))
+        .collect();
+    assert_eq!(found.len(), 3, "{}", report);
+    assert_eq!(found[0].0, IssueCode::MissingElement);
+    assert!(found[0].1.contains("BIG04"), "{}", found[0].1);
+    assert_eq!(found[1].0, IssueCode::InvalidCode);
+    assert!(found[1].1.contains("N101"), "{}", found[1].1);
+    assert_eq!(found[2].0, IssueCode::MissingSegment);
+
+    // A schema for another version does not apply
+    let mut other_version = schema;
+    other_version.version = Some("005010".to_string());
+    let parser = X12Parser::default().with_schemas(SchemaRegistry::empty().with_schema(other_version));
+    assert!(parser.validate_report(&interchange).is_empty());
+
+    assert!(matches!(
+        TransactionSetSchema::from_json(r#"{"id": "810", "segments": {"BIG": {"elements": [{"type": "XX"}]}}}"#),
+        Err(EdiError::InvalidSchema(_))
+    ));
+}
+
+#[test]
+fn test_element_data_types_and_lengths() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*BUYERID        *01*SELLERID       *230101*1300*U*00401*000000002*0*T*>~GS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~ST*850*0001~BEG*00*SA*PO-0000000000000000000001**2023011~DTM*002*230115*12~PO1*1*-2.5*EA*.75~PO1*2*1.2.3*EA*1E3~CTT*1.5*-3~SE*7*0001~GE*1*2~IEA*1*000000002~";
+
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+    let found: Vec<_> = report.issues().iter()
+        .map(|issue| (issue.code, issue.location.to_string().split(',').nth(2).unwrap().trim().to_string()))
+        .collect();
+
+    let expected = [
+        (IssueCode::ElementTooLong, "BEG03"),
+        (IssueCode::InvalidDate, "BEG05"),
+        (IssueCode::ElementTooShort, "DTM02"),
+        (IssueCode::InvalidTime, "DTM03"),
+        (IssueCode::InvalidDataType, "PO102"),
+        (IssueCode::InvalidDataType, "PO104"),
+        (IssueCode::InvalidDataType, "CTT01"),
+    ];
+    let expected: Vec<_> = expected.iter().map(|(code, reference)| (*code, reference.to_string())).collect();
+    assert_eq!(found, expected, "{}", report);
+
+    assert_eq!(report.issues()[0].error, EdiError::ElementTooLong {
+        reference: "BEG03".to_string(),
+        value: "PO-0000000000000000000001".to_string(),
+        max: 22,
+    });
+    assert_eq!(report.issues()[6].error.to_string(), "CTT01 value 1.5 is not a valid N0");
+}
+
+#[test]
+fn test_syntax_notes() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*BUYERID        *01*SELLERID       *230101*1300*U*00401*000000002*0*T*>~GS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~ST*850*0001~BEG*00*SA*PO-001**20230101~REF*VR~N1*ST*ABC Corporation*92~N1*BT*ABC Corporation*92*12345~SE*6*0001~GE*1*2~IEA*1*000000002~";
+
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+    let found: Vec<_> = report.issues().iter().map(|issue| (issue.code, &issue.error)).collect();
+    assert_eq!(found, vec![
+        (IssueCode::ConditionalElementMissing, &EdiError::SyntaxNoteViolated {
+            note: "REFR0203".to_string(),
+            reference: "REF02".to_string(),
+        }),
+        (IssueCode::ConditionalElementMissing, &EdiError::SyntaxNoteViolated {
+            note: "N1P0304".to_string(),
+            reference: "N104".to_string(),
+        }),
+    ]);
+    assert_eq!(report.issues()[1].location.segment_index, Some(3));
+    assert_eq!(report.issues()[1].location.element, Some(4));
+
+    let note = SyntaxNote::try_from("E020304".to_string()).unwrap();
+    assert_eq!(note, SyntaxNote { rule: SyntaxRule::Exclusion, elements: vec![2, 3, 4] });
+    assert_eq!(note.violation(|position| position != 3), Some(4));
+    assert_eq!(note.violation(|position| position == 3), None);
+    assert!(SyntaxNote::try_from("P03".to_string()).is_err());
+    assert_eq!(note.to_string(), "E020304");
+}
+
+#[test]
+fn test_segment_structure() {
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*BUYERID        *01*SELLERID       *230101*1300*U*00401*000000002*0*T*>~GS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~ST*850*0001~BEG*00*SA*PO-1**20230101~PER*BD*A~PER*BD*B~PER*BD*C~PER*BD*D~DTM*002*20230115~REF*VR*X~MSG*HELLO~ZZZ*1~PO1*1*1*EA*1~CTT*1~CTT*1~SE*14*0001~GE*1*2~IEA*1*000000002~";
+
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+    let found: Vec<_> = report.issues().iter()
+        .map(|issue| (issue.code, issue.location.segment_index.unwrap()))
+        .collect();
+    assert_eq!(found, vec![
+        (IssueCode::MaxUseExceeded, 5),
+        (IssueCode::SegmentOutOfOrder, 7),
+        (IssueCode::UnexpectedSegment, 8),
+        (IssueCode::UndefinedSegment, 9),
+        (IssueCode::LoopMaxUseExceeded, 12),
+    ], "{}", report);
+    assert_eq!(report.issues()[0].error, EdiError::SegmentMaxUseExceeded { id: "PER".to_string(), max_use: 3 });
+    assert_eq!(report.issues()[4].error, EdiError::LoopMaxUseExceeded { id: "CTT".to_string(), max_use: 1 });
+
+    // A loop closed without its mandatory segments
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~LM*ZZ~TDS*0~SE*5*0001~GE*1*1~IEA*1*000000001~";
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+    assert_eq!(report.len(), 1, "{}", report);
+    let missing = &report.issues()[0];
+    assert_eq!(missing.code, IssueCode::MissingSegment);
+    assert_eq!(missing.error, EdiError::MissingRequiredSegment("LQ in loop LM".to_string()));
+    assert_eq!(missing.location.segment_id.as_deref(), Some("LQ"));
+    assert_eq!(missing.location.segment_index, Some(3));
+
+    // A transaction cut off inside the loop, without its SE
+    let input = input.replace("TDS*0~SE*5*0001~", "");
+    let interchange = parser.clone().with_mode(ParseMode::Lenient).parse(&input).unwrap();
+    let report = parser.validate_report(&interchange);
+    let found: Vec<_> = report.issues().iter()
+        .filter(|issue| issue.code == IssueCode::MissingSegment)
+        .map(|issue| (issue.error.clone(), issue.location.segment_index))
+        .collect();
+    assert_eq!(found, vec![
+        (EdiError::MissingRequiredSegment("LQ in loop LM".to_string()), Some(3)),
+        (EdiError::MissingRequiredSegment("SE in transaction 810".to_string()), Some(3)),
+    ], "{}", report);
+}
+
+#[test]
+fn test_calendar_dates_and_times() {
+    use chrono::{NaiveDate, NaiveTime};
+
+    let parser = X12Parser::default();
+    let input = "ISA*00*          *00*          *01*BUYERID        *01*SELLERID       *230101*1300*U*00401*000000002*0*T*>~GS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~ST*850*0001~BEG*00*SA*PO-1**20230230~DTM*002*20231399~DTM*010*20230115*2460~DTM*036****RD8*20230131-20230101~DTM*037****RD8*20230101-20230131~SE*7*0001~GE*1*2~IEA*1*000000002~";
+
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+    let found: Vec<_> = report.issues().iter().map(|issue| (issue.code, issue.error.to_string())).collect();
+    assert_eq!(found, vec![
+        (IssueCode::InvalidDate, "BEG05 value 20230230 is not a valid date".to_string()),
+        (IssueCode::InvalidDate, "DTM02 value 20231399 is not a valid date".to_string()),
+        (IssueCode::InvalidTime, "DTM03 value 2460 is not a valid time".to_string()),
+        (IssueCode::InvalidDate, "DTM06 value 20230131-20230101 is not a valid date".to_string()),
+    ]);
+
+    let dtm = Segment::new("DTM".to_string(), vec!["002".to_string(), "230115".to_string(), "1230455".to_string()]);
+    assert_eq!(dtm.date(2), NaiveDate::from_ymd_opt(2023, 1, 15));
+    assert_eq!(dtm.time(3), NaiveTime::from_hms_milli_opt(12, 30, 45, 500));
+    assert_eq!(dtm.date(3), None);
+
+    let transaction = &interchange.functional_groups[0].transactions[0];
+    let range = transaction.segments[5].date_period(5, 6).unwrap();
+    assert_eq!(range, DatePeriod::DateRange(
+        NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2023, 1, 31).unwrap(),
+    ));
+    assert_eq!(transaction.segments[4].date_period(5, 6), None);
+}
+
+#[test]
+fn test_code_list_registry() {
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~N1*RI*Remit Co~N1*VN*Vendor Co~N1*ZA*Other Co~SE*6*0001~GE*1*1~IEA*1*000000001~";
+    let interchange = X12Parser::default().parse(input).unwrap();
+
+    // RI and VN are standard entity codes; ZA is not
+    let report = X12Parser::default().validate_report(&interchange);
+    assert_eq!(report.len(), 1, "{}", report);
+    assert_eq!(report.issues()[0].error, EdiError::InvalidCodeValue { reference: "N101".to_string(), value: "ZA".to_string() });
+
+    // A partner that uses ZA and does not accept VN
+    let mut codes = CodeListRegistry::default();
+    codes.extend("98", ["ZA"]);
+    codes.restrict("98", ["RI", "ZA", "ST", "BT"]);
+    let report = X12Parser::default().with_code_lists(codes).validate_report(&interchange);
+    let values: Vec<_> = report.issues().iter().map(|issue| issue.location.segment_index).collect();
+    assert_eq!(values, vec![Some(3)], "{}", report);
+
+    // A list for the group's version takes precedence
+    let mut codes = CodeListRegistry::default();
+    codes.load_json(r#"{"lists": [{"element": "98", "version": "004010", "codes": ["RI", "VN", "ZA"]}]}"#).unwrap();
+    assert!(X12Parser::default().with_code_lists(codes.clone()).validate(&interchange).is_ok());
+    assert_eq!(codes.get("98", Some("005010")).unwrap().version, None);
+
+    codes.register(CodeList { element: "98".to_string(), version: None, name: None, codes: ["ST".to_string()].into() });
+    assert_eq!(codes.contains("98", Some("005010"), "RI"), Some(false));
+    assert!(CodeListRegistry::empty().contains("98", None, "XX").is_none());
+}
+
+/// Rejects REF segments whose reference number is not all digits.
+struct NumericReference;
+
+impl SegmentValidator for NumericReference {
+    fn validate(&self, segment: &Segment, location: &IssueLocation, report: &mut ValidationReport) {
+        let value = segment.element(2).unwrap_or_default();
+        if !value.chars().all(|c| c.is_ascii_digit()) {
+            report.error(
+                IssueCode::InvalidSegment,
+                location.clone().element(2),
+                EdiError::ValidationError(format!("REF02 {} is not numeric", value)),
+            );
+        }
+    }
+}
+
+#[test]
+fn test_custom_validators() {
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*PO*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*850*0001~BEG*00*SA*PO-001**20230101~REF*DP*A1~PO1*1*100*EA*10.50**BP*ITEM-001~SE*5*0001~GE*1*1~IEA*1*000000001~";
+    let parser = X12Parser::default();
+    let interchange = parser.parse(input).unwrap();
+    assert!(parser.validate(&interchange).is_ok());
+
+    // Segment validators only run for their transaction set and segment ID
+    let report = X12Parser::default()
+        .with_segment_validator("850", "REF", NumericReference)
+        .with_segment_validator("810", "BEG", NumericReference)
+        .validate_report(&interchange);
+    assert_eq!(report.len(), 1, "{}", report);
+    let issue = &report.issues()[0];
+    assert_eq!(issue.code, IssueCode::InvalidSegment);
+    assert_eq!(issue.location.segment_id.as_deref(), Some("REF"));
+    assert_eq!(issue.location.segment_index, Some(2));
+    assert_eq!(issue.location.element, Some(2));
+
+    // Transaction validators see the whole transaction, and run after the
+    // built-in checks
+    let validator = Validator::default()
+        .with_schema(TransactionSetSchema::builtin("850").unwrap().clone())
+        .with_transaction_validator("850", |transaction: &Transaction, location: &IssueLocation, report: &mut ValidationReport| {
+            if !transaction.segments.iter().any(|segment| segment.id == "CTT") {
+                report.warning(
+                    IssueCode::MissingSegment,
+                    location.clone(),
+                    EdiError::MissingRequiredSegment("CTT".to_string()),
+                );
+            }
+        });
+    let report = validator.validate_report(&interchange);
+    assert_eq!(report.len(), 1, "{}", report);
+    assert_eq!(report.issues()[0].severity, Severity::Warning);
+    assert!(validator.validate(&interchange).is_ok());
+
+    let parser = X12Parser::default().with_validator(validator);
+    assert_eq!(parser.validate_report(&interchange).len(), 1);
+}
+
+#[test]
+fn test_partner_profiles() {
+    let purchase_order = |sender: &str, body: &str| {
+        format!("ISA*00*          *00*          *01*{:<15}*01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*PO*{}*RECEIVERID*20230101*1253*1*X*004010~ST*850*0001~BEG*01*SA*PO-001**20230101~{}SE*4*0001~GE*1*1~IEA*1*000000001~", sender, sender, body)
+    };
+    let parser = X12Parser::default()
+        .with_profile(
+            PartnerProfile::new("walmart")
+                .with_selector(PartnerSelector::InterchangeSender("WALMART".to_string()))
+                .with_loop_usage("850", "N1", Usage::Mandatory)
+                .with_codes("353", ["00"])
+                .with_segment_validator("850", "N1", |segment: &Segment, location: &IssueLocation, report: &mut ValidationReport| {
+                    if segment.element(1) == Some("ST") && segment.element(4).unwrap_or_default().is_empty() {
+                        report.error(
+                            IssueCode::MissingElement,
+                            location.clone().element(4),
+                            EdiError::MissingElement { reference: "N104".to_string() },
+                        );
+                    }
+                }),
+        )
+        .with_profile(
+            PartnerProfile::new("no-allowances")
+                .with_selector(PartnerSelector::GroupSender("ACME".to_string()))
+                .with_segment_usage("850", "SAC", Usage::NotUsed)
+                .with_element_usage("850", "BEG", 4, Usage::NotUsed)
+                // Positions are 1-based, so this one names no element
+                .with_element_usage("850", "BEG", 0, Usage::NotUsed),
+        );
+
+    // Other senders are only held to the standard
+    let interchange = parser.parse(&purchase_order("OTHER", "N1*ST*Store~")).unwrap();
+    assert!(parser.validate(&interchange).is_ok());
+
+    // Walmart requires the N1 loop, N104 on N1*ST and BEG01 of 00
+    let interchange = parser.parse(&purchase_order("WALMART", "N1*ST*Store~")).unwrap();
+    let found: Vec<_> = parser.validate_report(&interchange).issues().iter()
+        .map(|issue| (issue.code, issue.location.segment_index, issue.location.element))
+        .collect();
+    assert_eq!(found, vec![
+        (IssueCode::InvalidCode, Some(1), Some(1)),
+        (IssueCode::MissingElement, Some(2), Some(4)),
+    ]);
+
+    // Every group from the partner gets the profile
+    let single = purchase_order("WALMART", "N1*ST*Store~");
+    let (groups, iea) = single.split_at(single.find("IEA*").unwrap());
+    let second = &groups[groups.find("GS*").unwrap()..];
+    let input = format!("{}{}{}", groups, second.replace("*1*X*", "*2*X*").replace("GE*1*1~", "GE*1*2~"), iea.replace("IEA*1*", "IEA*2*"));
+    let interchange = parser.parse(&input).unwrap();
+    let found: Vec<_> = parser.validate_report(&interchange).issues().iter()
+        .map(|issue| (issue.code, issue.location.group_index))
+        .collect();
+    assert_eq!(found, vec![
+        (IssueCode::InvalidCode, Some(0)),
+        (IssueCode::MissingElement, Some(0)),
+        (IssueCode::InvalidCode, Some(1)),
+        (IssueCode::MissingElement, Some(1)),
+    ]);
+    let interchange = parser.parse(&purchase_order("WALMART", "DTM*002*20230115~")).unwrap();
+    let report = parser.validate_report(&interchange);
+    assert!(report.issues().iter().any(|issue| issue.error == EdiError::MissingRequiredSegment("N1 in transaction 850".to_string())), "{}", report);
+
+    // ACME is selected by GS02 and does not accept SAC or BEG04
+    let interchange = parser.parse(&purchase_order("ACME", "SAC*A*H850~").replace("PO-001**", "PO-001*1*")).unwrap();
+    let group = &interchange.functional_groups[0];
+    assert_eq!(parser.validator().profile_for(&interchange, group).unwrap().name, "no-allowances");
+    let found: Vec<_> = parser.validate_report(&interchange).issues().iter()
+        .map(|issue| (issue.code, issue.location.segment_index))
+        .collect();
+    assert_eq!(found, vec![(IssueCode::ElementNotUsed, Some(1)), (IssueCode::SegmentNotUsed, Some(2))]);
+}
+
+#[test]
+fn test_control_totals() {
+    let parser = X12Parser::default();
+    for file in ["tests/test_files/sample_850_extended.edi", "tests/test_files/sample_810.edi"] {
+        let interchange = parser.parse(&fs::read_to_string(file).unwrap()).unwrap();
+        assert!(parser.validate(&interchange).is_ok(), "{}", file);
+    }
+
+    // Three PO1 lines of 100, 50 and 2.5 units
+    let input = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*PO*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*850*0001~BEG*00*SA*PO-001**20230101~PO1*1*100*EA*10.50~PO1*2*50*EA*25~PO1*3*2.5*LB*4~CTT*2*150~SE*7*0001~GE*1*1~IEA*1*000000001~";
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+    let errors: Vec<_> = report.issues().iter().map(|issue| (issue.code, issue.location.element, issue.error.clone())).collect();
+    assert_eq!(errors, vec![
+        (IssueCode::TotalMismatch, Some(1), EdiError::TotalMismatch {
+            reference: "CTT01".to_string(),
+            expected: "3".to_string(),
+            actual: "2".to_string(),
+            total: "number of PO1 line items".to_string(),
+        }),
+        (IssueCode::TotalMismatch, Some(2), EdiError::TotalMismatch {
+            reference: "CTT02".to_string(),
+            expected: "152.5".to_string(),
+            actual: "150".to_string(),
+            total: "hash total of PO102 quantities".to_string(),
+        }),
+    ]);
+    assert_eq!(report.issues()[0].location.segment_index, Some(5));
+    assert!(report.issues()[1].error.to_string().starts_with("CTT02 is 150 but the hash total of PO102 quantities is 152.5"));
+    assert!(X12Parser::default().with_validator(Validator::new()).validate(&interchange).is_err());
+    assert!(X12Parser::default().with_validator(Validator::without_business_rules()).validate(&interchange).is_ok());
+
+    // 3 x 33.333 rounds to 100.00; a 15.00 charge and a 5.00 allowance
+    let invoice = |tds: &str| format!("ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~IT1*1*3*EA*33.333~IT1*2*1*EA*0.5~TDS*{}~SAC*C*D240***1500~SAC*A*C310***500~SAC*N*H850***999~SE*9*0001~GE*1*1~IEA*1*000000001~", tds);
+    let interchange = parser.parse(&invoice("11050")).unwrap();
+    assert!(parser.validate(&interchange).is_ok(), "{}", parser.validate_report(&interchange));
+    let interchange = parser.parse(&invoice("11000")).unwrap();
+    let error = parser.validate(&interchange).unwrap_err();
+    assert_eq!(error.root(), &EdiError::TotalMismatch {
+        reference: "TDS01".to_string(),
+        expected: "11050".to_string(),
+        actual: "11000".to_string(),
+        total: "total of IT1 lines and SAC charges in cents".to_string(),
+    });
+
+    // Line totals that overflow are left to the element length checks
+    let oversized = "ISA*00*          *00*          *01*SENDERID       *01*RECEIVERID     *230101*1253*U*00401*000000001*0*T*>~GS*IN*SENDERID*RECEIVERID*20230101*1253*1*X*004010~ST*810*0001~BIG*20230101*INV-001~IT1*1*999999999999999999*EA*999999999999999999~IT1*2*999999999999999999*EA*999999999999999999~TDS*1~SE*6*0001~GE*1*1~IEA*1*000000001~";
+    let interchange = parser.parse(oversized).unwrap();
+    let report = parser.validate_report(&interchange);
+    assert!(!report.is_valid());
+    assert!(report.issues().iter().all(|issue| issue.code != IssueCode::TotalMismatch), "{}", report);
+    assert!(report.issues().iter().any(|issue| issue.code == IssueCode::ElementTooLong), "{}", report);
+}
+
+#[test]
+fn test_functional_acknowledgment() {
+    let parser = X12Parser::default();
+    let date_time = chrono::NaiveDate::from_ymd_opt(2023, 1, 2).unwrap().and_hms_opt(9, 30, 0).unwrap();
+
+    // The second order has an invalid BEG01 and a PO1 with a unit of measure
+    // but no quantity; the third has the wrong segment count
+    let input = "ISA*00*          *00*          *01*BUYERID        *ZZ*SELLERID       *230101*1253*U*00401*000000007*1*T*>~GS*PO*BUYERAPP*SELLERAPP*20230101*1253*12*X*004010~ST*850*0001~BEG*00*SA*PO-001**20230101~SE*3*0001~ST*850*0002~BEG*99*SA*PO-002**20230101~PO1*1**EA*10~SE*4*0002~ST*850*0003~BEG*00*SA*PO-003**20230101~SE*9*0003~GE*3*12~IEA*1*000000007~";
+    let interchange = parser.parse(input).unwrap();
+    let report = parser.validate_report(&interchange);
+    let acknowledgment = FunctionalAcknowledgment::new(&interchange, &report)
+        .with_interchange_control_number(501)
+        .with_group_control_number(77)
+        .with_date_time(date_time);
+
+    assert_eq!(acknowledgment.to_edi(), [
+        "ISA*00*          *00*          *ZZ*SELLERID       *01*BUYERID        *230102*0930*U*00401*000000501*0*T*>~",
+        "GS*FA*SELLERAPP*BUYERAPP*20230102*0930*77*X*004010~",
+        "ST*997*0001~",
+        "AK1*PO*12~",
+        "AK2*850*0001~",
+        "AK5*A~",
+        "AK2*850*0002~",
+        "AK3*BEG*2**8~",
+        "AK4*1**7*99~",
+        "AK3*PO1*3**8~",
+        "AK4*2**2~",
+        "AK5*R*5~",
+        "AK2*850*0003~",
+        "AK5*R*4~",
+        "AK9*P*3*3*1~",
+        "SE*14*0001~",
+        "GE*1*77~",
+        "IEA*1*000000501~",
+    ].concat(), "{}", report);
+
+    // The acknowledgment is itself a valid interchange
+    let reply = parser.parse(&acknowledgment.to_edi()).unwrap();
+    assert!(parser.validate(&reply).is_ok());
+    assert_eq!(reply.isa_header().sender_id(), "SELLERID");
+    assert_eq!(reply.isa_header().receiver_id(), "BUYERID");
+
+    // A clean group is accepted as a whole
+    let interchange = parser.parse(&fs::read_to_string("tests/test_files/sample_850.edi").unwrap()).unwrap();
+    let report = parser.validate_report(&interchange);
+    let transaction = FunctionalAcknowledgment::new(&interchange, &report).transaction(0, "0001").unwrap();
+    let ids: Vec<_> = transaction.segments.iter().map(|segment| segment.id.as_str()).collect();
+    assert_eq!(ids, vec!["ST", "AK1", "AK2", "AK5", "AK9", "SE"]);
+    assert_eq!(transaction.segments[4].elements, vec!["A", "1", "1", "1"]);
+
+    // Errors without a segment still reject the transaction
+    let parser = X12Parser::default().with_transaction_validator("850", |_: &Transaction, location: &IssueLocation, report: &mut ValidationReport| {
+        report.error(
+            IssueCode::InvalidSegment,
+            location.clone(),
+            EdiError::ValidationError("order is not approved".to_string()),
+        );
+    });
+    let report = parser.validate_report(&interchange);
+    assert!(!report.is_valid());
+    let transaction = FunctionalAcknowledgment::new(&interchange, &report).transaction(0, "0001").unwrap();
+    let ids: Vec<_> = transaction.segments.iter().map(|segment| segment.id.as_str()).collect();
+    assert_eq!(ids, vec!["ST", "AK1", "AK2", "AK5", "AK9", "SE"]);
+    assert_eq!(transaction.segments[3].elements, vec!["R", "5"]);
+    assert_eq!(transaction.segments[4].elements, vec!["R", "1", "1", "0"]);
+
+    // Transactions are told apart by position even when they share ST02, and
+    // a total mismatch rejects its transaction without an AK3
+    let input = "ISA*00*          *00*          *01*BUYERID        *01*SELLERID       *230101*1300*U*00401*000000002*0*T*>~GS*PO*BUYERID*SELLERID*20230101*1300*2*X*004010~ST*850*0001~BEG*00*SA*PO-001**20230101~PO1*1*100*EA*10.50~CTT*2~SE*5*0001~ST*850*0001~BEG*00*SA*PO-002**20230101~PO1*1*100*EA*10.50~CTT*1~SE*5*0001~GE*2*2~IEA*1*000000002~";
+    let interchange = X12Parser::default().parse(input).unwrap();
+    let report = X12Parser::default().validate_report(&interchange);
+    assert_eq!(report.issues()[0].code, IssueCode::TotalMismatch, "{}", report);
+    let transaction = FunctionalAcknowledgment::new(&interchange, &report).transaction(0, "0001").unwrap();
+    let segments: Vec<_> = transaction.segments[2..].iter()
+        .map(|segment| format!("{}*{}", segment.id, segment.elements.join("*")))
+        .collect();
+    assert_eq!(segments, vec!["AK2*850*0001", "AK5*R*5", "AK2*850*0001", "AK5*A", "AK9*P*2*2*1", "SE*8*0001"]);
+}